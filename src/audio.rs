@@ -1,29 +1,52 @@
-use crate::actions::Actions;
+use crate::events::{ComboLevelUp, CorrectKey, TrashExploded, TrashLanded, WordCompleted, WrongKey};
+use crate::game::WINDOW_WIDTH;
 use crate::loading::AudioAssets;
-use crate::GameState;
+use crate::score::ComboModifier;
+use crate::settings::Settings;
+use crate::{GameState, PauseState};
 use bevy::prelude::*;
+use bevy::utils::HashSet;
 use bevy_kira_audio::prelude::*;
 
 pub struct InternalAudioPlugin;
 
+// The music volume while paused, relative to the volume from the settings
+const DUCKED_MUSIC_VOLUME_SCALE: f64 = 0.25;
+// Every combo level plays the keystrokes a bit higher, up to `MAX_KEYSTROKE_PLAYBACK_RATE`
+const KEYSTROKE_PITCH_STEP: f64 = 0.06;
+const MAX_KEYSTROKE_PLAYBACK_RATE: f64 = 2.0;
+
 // This plugin is responsible to control the game audio
 impl Plugin for InternalAudioPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(AudioPlugin)
-            .add_systems(OnEnter(GameState::Menu), create_audio)
+            .add_audio_channel::<SfxChannel>()
+            .add_systems(OnExit(GameState::Loading), create_audio)
+            .add_systems(OnEnter(GameState::Menu), stop_game_audio)
             .add_systems(OnEnter(GameState::Playing), start_game_audio)
-            .add_systems(OnEnter(GameState::GameOver), start_game_over_audio);
-            // .add_systems(
-            //     Update,
-            //     control_flying_sound
-            //         .after(set_movement_actions)
-            //         .run_if(in_state(GameState::Playing)),
-            // );
+            .add_systems(OnEnter(GameState::GameOver), start_game_over_audio)
+            .add_systems(OnEnter(PauseState::Paused), duck_game_audio)
+            .add_systems(OnExit(PauseState::Paused), unduck_game_audio)
+            .add_systems(Update, (
+                apply_music_volume.run_if(in_state(GameState::Settings)),
+                play_sound_effects,
+            ));
     }
 }
 
+/// A short sound played on the effects channel in response to the gameplay events
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum SoundEffect {
+    Keystroke,
+    Mistake,
+    WordCleared,
+    ComboUp,
+    Explosion,
+    Landing,
+}
+
 #[derive(Resource)]
-struct FlyingAudio(Handle<AudioInstance>);
+struct SfxChannel;
 
 #[derive(Resource)]
 struct GameAudio(Handle<AudioInstance>);
@@ -31,30 +54,69 @@ struct GameAudio(Handle<AudioInstance>);
 #[derive(Resource)]
 struct GameOverAudio(Handle<AudioInstance>);
 
-fn create_audio(mut commands: Commands, audio_assets: Res<AudioAssets>, audio: Res<Audio>) {
+fn create_audio(mut commands: Commands, audio_assets: Res<AudioAssets>, audio: Res<Audio>, settings: Res<Settings>) {
     audio.pause();
-    // let handle = audio
-    //     .play(audio_assets.flying.clone())
-    //     .looped()
-    //     .with_volume(0.3)
-    //     .handle();
-    // commands.insert_resource(FlyingAudio(handle));
-
     let handle = audio
         .play(audio_assets.play.clone())
         .looped()
-        .with_volume(0.3)
+        .with_volume(settings.music_volume)
         .handle();
     commands.insert_resource(GameAudio(handle));
 
     audio.pause();
     let handle = audio
         .play(audio_assets.game_over.clone())
-        .with_volume(0.3)
+        .with_volume(settings.music_volume)
         .handle();
     commands.insert_resource(GameOverAudio(handle));
 }
 
+fn stop_game_audio(
+    game_audio: Res<GameAudio>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+) {
+    if let Some(instance) = audio_instances.get_mut(&game_audio.0) {
+        instance.pause(AudioTween::default());
+    }
+}
+
+fn duck_game_audio(
+    game_audio: Res<GameAudio>,
+    settings: Res<Settings>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+) {
+    if let Some(instance) = audio_instances.get_mut(&game_audio.0) {
+        instance.set_volume(settings.music_volume * DUCKED_MUSIC_VOLUME_SCALE, AudioTween::default());
+    }
+}
+
+fn unduck_game_audio(
+    game_audio: Res<GameAudio>,
+    settings: Res<Settings>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+) {
+    if let Some(instance) = audio_instances.get_mut(&game_audio.0) {
+        instance.set_volume(settings.music_volume, AudioTween::default());
+    }
+}
+
+fn apply_music_volume(
+    game_audio: Res<GameAudio>,
+    game_over_audio: Res<GameOverAudio>,
+    settings: Res<Settings>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    for handle in [&game_audio.0, &game_over_audio.0] {
+        if let Some(instance) = audio_instances.get_mut(handle) {
+            instance.set_volume(settings.music_volume, AudioTween::default());
+        }
+    }
+}
+
 fn start_game_audio(
     game_audio: Res<GameAudio>,
     game_over_audio: Res<GameOverAudio>,
@@ -90,25 +152,84 @@ fn start_game_over_audio(
 }
 
 
-#[allow(dead_code)]
-fn control_flying_sound(
-    actions: Res<Actions>,
-    audio: Res<FlyingAudio>,
-    mut audio_instances: ResMut<Assets<AudioInstance>>,
+impl SoundEffect {
+    /// The volume of the effect before the effects volume from the settings is applied
+    fn volume(&self) -> f64 {
+        match self {
+            Self::Keystroke => 0.4,
+            Self::Mistake => 0.6,
+            Self::WordCleared => 0.7,
+            Self::ComboUp => 0.8,
+            Self::Explosion => 1.0,
+            Self::Landing => 0.5,
+        }
+    }
+
+    fn source(&self, audio_assets: &AudioAssets) -> Handle<AudioSource> {
+        match self {
+            Self::Keystroke => audio_assets.keystroke.clone(),
+            Self::Mistake => audio_assets.mistake.clone(),
+            Self::WordCleared => audio_assets.word_cleared.clone(),
+            Self::ComboUp => audio_assets.combo_up.clone(),
+            Self::Explosion => audio_assets.explosion.clone(),
+            Self::Landing => audio_assets.landing.clone(),
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn play_sound_effects(
+    mut correct_key_events: EventReader<CorrectKey>,
+    mut wrong_key_events: EventReader<WrongKey>,
+    mut word_completed_events: EventReader<WordCompleted>,
+    mut combo_level_up_events: EventReader<ComboLevelUp>,
+    mut trash_exploded_events: EventReader<TrashExploded>,
+    mut trash_landed_events: EventReader<TrashLanded>,
+    sfx_channel: Res<AudioChannel<SfxChannel>>,
+    audio_assets: Option<Res<AudioAssets>>,
+    settings: Res<Settings>,
+    combo_modifier: Res<ComboModifier>,
 ) {
-    if let Some(instance) = audio_instances.get_mut(&audio.0) {
-        match instance.state() {
-            PlaybackState::Paused { .. } => {
-                if actions.player_movement.is_some() {
-                    instance.resume(AudioTween::default());
-                }
-            }
-            PlaybackState::Playing { .. } => {
-                if actions.player_movement.is_none() {
-                    instance.pause(AudioTween::default());
-                }
-            }
-            _ => {}
+    // Explosions are panned toward the side of the playfield they went off in
+    let explosion_x = trash_exploded_events.read().last().map(|trash_exploded| trash_exploded.position.x);
+    // The keystrokes are pitched with the combo of whoever typed last
+    let keystroke_player = correct_key_events.read().last().map(|correct_key| correct_key.player);
+
+    // A linked group or a pile up sends many events at once, each effect is played once per frame
+    let mut sound_effects = HashSet::new();
+    for (sound_effect, count) in [
+        (SoundEffect::Keystroke, keystroke_player.iter().count()),
+        // A buffer emptied under a lost word isn't a key the player got wrong
+        (SoundEffect::Mistake, wrong_key_events.read().filter(|wrong_key| wrong_key.letter.is_some()).count()),
+        (SoundEffect::WordCleared, word_completed_events.read().count()),
+        (SoundEffect::ComboUp, combo_level_up_events.read().count()),
+        (SoundEffect::Explosion, explosion_x.iter().count()),
+        (SoundEffect::Landing, trash_landed_events.read().count()),
+    ] {
+        if count > 0 {
+            sound_effects.insert(sound_effect);
         }
     }
+
+    let Some(audio_assets) = audio_assets else {
+        return;
+    };
+
+    for sound_effect in sound_effects {
+        let playback_rate = match sound_effect {
+            SoundEffect::Keystroke => (1.0 + KEYSTROKE_PITCH_STEP * combo_modifier.0[keystroke_player.unwrap_or_default()].saturating_sub(1) as f64)
+                .min(MAX_KEYSTROKE_PLAYBACK_RATE),
+            _ => 1.0,
+        };
+        let panning = match (sound_effect, explosion_x) {
+            (SoundEffect::Explosion, Some(x)) => (0.5 + x as f64 / WINDOW_WIDTH as f64).clamp(0.0, 1.0),
+            _ => 0.5,
+        };
+
+        sfx_channel
+            .play(sound_effect.source(&audio_assets))
+            .with_volume(sound_effect.volume() * settings.sfx_volume)
+            .with_playback_rate(playback_rate)
+            .with_panning(panning);
+    }
 }
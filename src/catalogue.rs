@@ -0,0 +1,145 @@
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext, io::Reader};
+use bevy::prelude::*;
+use bevy::reflect::TypePath;
+use bevy::utils::BoxedFuture;
+use rand::distributions::WeightedIndex;
+use rand::prelude::Distribution;
+use rand::Rng;
+use serde::Deserialize;
+use thiserror::Error;
+
+// The trash kinds are defined in `assets/trash.catalogue.ron`,
+// adding a new kind of trash only requires a new entry there and a texture.
+
+
+/// What the trash is made of, it decides the recycling bin it belongs in
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Material {
+    Plastic,
+    Glass,
+    Paper,
+    Metal,
+    Organic,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TrashKind {
+    pub name: String,
+    pub texture: String,
+    pub material: Material,
+    /// Half of the collider width and height
+    pub half_extents: (f32, f32),
+    pub mass: f32,
+    pub restitution: f32,
+    pub spawn_weight: f32,
+    /// Drawn at this size instead of the size of the texture, for the bosses that reuse small textures
+    #[serde(default)]
+    pub sprite_size: Option<(f32, f32)>,
+    #[serde(skip)]
+    pub texture_handle: Handle<Image>,
+}
+
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct TrashCatalogue {
+    pub kinds: Vec<TrashKind>,
+    /// The large trash that takes a sequence of words to clear, there are no bosses without any
+    #[serde(default)]
+    pub bosses: Vec<TrashKind>,
+}
+
+#[derive(Default)]
+pub struct TrashCatalogueLoader;
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum CatalogueLoaderError {
+    #[error("Could not read the catalogue: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse the catalogue: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+    #[error("The catalogue doesn't contain any trash with a positive spawn weight")]
+    Empty,
+}
+
+
+impl Material {
+    /// In the order of the bins along the floor
+    pub const ALL: [Material; 5] = [
+        Material::Plastic,
+        Material::Glass,
+        Material::Paper,
+        Material::Metal,
+        Material::Organic,
+    ];
+
+    pub fn color(&self) -> Color {
+        match self {
+            Self::Plastic => Color::rgb(0.95, 0.8, 0.1),
+            Self::Glass => Color::rgb(0.2, 0.65, 0.35),
+            Self::Paper => Color::rgb(0.2, 0.4, 0.85),
+            Self::Metal => Color::rgb(0.6, 0.6, 0.65),
+            Self::Organic => Color::rgb(0.55, 0.35, 0.15),
+        }
+    }
+}
+
+impl TrashKind {
+    pub fn size(&self) -> Vec2 {
+        Vec2::from(self.half_extents)
+    }
+
+    pub fn sprite_size(&self) -> Option<Vec2> {
+        self.sprite_size.map(Vec2::from)
+    }
+}
+
+impl TrashCatalogue {
+    /// Picks a random kind of trash according to the spawn weights
+    pub fn choose(&self, random: &mut impl Rng) -> &TrashKind {
+        let weights = WeightedIndex::new(self.kinds.iter().map(|kind| kind.spawn_weight))
+            .expect("the catalogue is validated when it's loaded");
+
+        &self.kinds[weights.sample(random)]
+    }
+
+    pub fn choose_boss(&self, random: &mut impl Rng) -> Option<&TrashKind> {
+        let weights = WeightedIndex::new(self.bosses.iter().map(|kind| kind.spawn_weight)).ok()?;
+
+        Some(&self.bosses[weights.sample(random)])
+    }
+}
+
+impl AssetLoader for TrashCatalogueLoader {
+    type Asset = TrashCatalogue;
+    type Settings = ();
+    type Error = CatalogueLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let mut catalogue = ron::de::from_bytes::<TrashCatalogue>(&bytes)?;
+
+            catalogue.kinds.retain(|kind| kind.spawn_weight > 0.0);
+            if catalogue.kinds.is_empty() {
+                return Err(CatalogueLoaderError::Empty);
+            }
+            catalogue.bosses.retain(|kind| kind.spawn_weight > 0.0);
+
+            for kind in catalogue.kinds.iter_mut().chain(catalogue.bosses.iter_mut()) {
+                kind.texture_handle = load_context.load(&kind.texture);
+            }
+
+            Ok(catalogue)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["catalogue.ron"]
+    }
+}
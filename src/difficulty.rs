@@ -0,0 +1,183 @@
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext, io::Reader};
+use bevy::prelude::*;
+use bevy::reflect::TypePath;
+use bevy::utils::BoxedFuture;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::seed::RunSeed;
+use crate::settings::Settings;
+
+// How the difficulty ramps up is defined in `assets/difficulty.profiles.ron`,
+// one profile per preset of the settings.
+
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum DifficultyPreset {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+/// A value that changes by `per_level` every difficulty level until it reaches `limit`
+#[derive(Deserialize, Clone, Copy, Debug, Default)]
+pub struct Curve {
+    pub start: f32,
+    pub per_level: f32,
+    pub limit: f32,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct DifficultyProfile {
+    /// Seconds between two difficulty levels
+    pub level_duration: f32,
+    /// Seconds between two spawns
+    pub spawn_interval: Curve,
+    /// How fast the trash starts falling, in pixels per second
+    pub fall_speed: Curve,
+    /// From 0 (short and common words) to 1 (the longest and rarest words)
+    pub word_difficulty: Curve,
+    /// The chance of a spawn to be a power up, from 0 to 1
+    pub power_up_chance: Curve,
+    /// Nothing spawns while this many words are on screen
+    pub max_trash: Curve,
+    /// Seconds between two bosses
+    pub boss_interval: f32,
+    /// How many words a boss takes to clear
+    pub boss_words: Curve,
+}
+
+/// The values of a profile at a given difficulty level
+#[derive(Clone, Debug)]
+pub struct Difficulty {
+    pub spawn_interval: f32,
+    pub fall_speed: f32,
+    pub word_difficulty: f32,
+    pub power_up_chance: f32,
+    pub max_trash: usize,
+    pub boss_words: usize,
+}
+
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct DifficultyProfiles {
+    pub easy: DifficultyProfile,
+    pub normal: DifficultyProfile,
+    pub hard: DifficultyProfile,
+}
+
+/// The profile of the current run, picked when it starts
+#[derive(Resource, Default)]
+pub struct RunDifficulty {
+    pub profile: DifficultyProfile,
+    /// Eases off instead of going up when the accuracy drops
+    pub adaptive: bool,
+}
+
+#[derive(Default)]
+pub struct DifficultyProfilesLoader;
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum DifficultyProfilesLoaderError {
+    #[error("Could not read the difficulty profiles: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse the difficulty profiles: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+    #[error("The {0} difficulty profile needs a positive level duration, spawn interval, max trash, boss interval and boss words")]
+    Invalid(&'static str),
+}
+
+
+impl DifficultyPreset {
+    pub fn next(&self, delta: i32) -> Self {
+        const PRESETS: [DifficultyPreset; 3] = [
+            DifficultyPreset::Easy,
+            DifficultyPreset::Normal,
+            DifficultyPreset::Hard,
+        ];
+        let index = PRESETS.iter().position(|preset| preset == self).unwrap_or(0) as i32;
+        PRESETS[(index + delta).rem_euclid(PRESETS.len() as i32) as usize]
+    }
+}
+
+impl Curve {
+    pub fn at(&self, level: u32) -> f32 {
+        let value = self.start + self.per_level * level as f32;
+        if self.per_level >= 0.0 { value.min(self.limit) } else { value.max(self.limit) }
+    }
+
+    /// The lowest value the curve ever takes
+    fn min(&self) -> f32 {
+        self.start.min(self.limit)
+    }
+}
+
+impl DifficultyProfile {
+    pub fn at(&self, level: u32) -> Difficulty {
+        Difficulty {
+            spawn_interval: self.spawn_interval.at(level),
+            fall_speed: self.fall_speed.at(level),
+            word_difficulty: self.word_difficulty.at(level).clamp(0.0, 1.0),
+            power_up_chance: self.power_up_chance.at(level).clamp(0.0, 1.0),
+            max_trash: self.max_trash.at(level).round() as usize,
+            boss_words: self.boss_words.at(level).round() as usize,
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        self.level_duration > 0.0 && self.spawn_interval.min() > 0.0 && self.max_trash.min() >= 1.0
+            && self.boss_interval > 0.0 && self.boss_words.min() >= 1.0
+    }
+}
+
+impl DifficultyProfiles {
+    pub fn get(&self, preset: DifficultyPreset) -> &DifficultyProfile {
+        match preset {
+            DifficultyPreset::Easy => &self.easy,
+            DifficultyPreset::Normal => &self.normal,
+            DifficultyPreset::Hard => &self.hard,
+        }
+    }
+}
+
+impl AssetLoader for DifficultyProfilesLoader {
+    type Asset = DifficultyProfiles;
+    type Settings = ();
+    type Error = DifficultyProfilesLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let profiles = ron::de::from_bytes::<DifficultyProfiles>(&bytes)?;
+
+            for (name, profile) in [("easy", &profiles.easy), ("normal", &profiles.normal), ("hard", &profiles.hard)] {
+                if !profile.is_valid() {
+                    return Err(DifficultyProfilesLoaderError::Invalid(name));
+                }
+            }
+
+            Ok(profiles)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["profiles.ron"]
+    }
+}
+
+
+// Daily runs are all played with the normal profile, without adapting to the player
+pub fn difficulty_preset(settings: &Settings, run_seed: &RunSeed) -> DifficultyPreset {
+    if run_seed.is_daily() { DifficultyPreset::Normal } else { settings.difficulty }
+}
+
+pub fn adaptive_difficulty(settings: &Settings, run_seed: &RunSeed) -> bool {
+    !run_seed.is_daily() && settings.adaptive_difficulty
+}
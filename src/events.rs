@@ -0,0 +1,101 @@
+use bevy::prelude::*;
+
+use crate::catalogue::Material;
+use crate::power_ups::PowerUp;
+use crate::typing::Player;
+
+pub struct GameplayEventsPlugin;
+
+/// The gameplay systems only report what happened through these events,
+/// scoring, audio, the UI and the run stats each subscribe to the ones they care about.
+impl Plugin for GameplayEventsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<CorrectKey>()
+        .add_event::<WrongKey>()
+        .add_event::<WordCompleted>()
+        .add_event::<BossCleared>()
+        .add_event::<TrashSorted>()
+        .add_event::<TrashLanded>()
+        .add_event::<PowerUpTriggered>()
+        .add_event::<TrashExploded>()
+        .add_event::<TrashDestroyed>()
+        .add_event::<ComboLevelUp>()
+        .add_event::<RunEnded>();
+    }
+}
+
+
+/// A typed letter that still matches at least one word
+#[derive(Event, Clone, Debug)]
+pub struct CorrectKey {
+    pub player: Player,
+}
+
+/// A typed letter that doesn't match any word, it's removed from the buffer
+#[derive(Event, Clone, Debug)]
+pub struct WrongKey {
+    pub player: Player,
+    /// `None` when the buffer was emptied while no word was left to match
+    pub letter: Option<char>,
+}
+
+#[derive(Event, Clone, Debug)]
+pub struct WordCompleted {
+    pub player: Player,
+    pub word: String,
+}
+
+/// The last word of a boss was typed, after a `WordCompleted` for each of its words
+#[derive(Event, Clone, Debug)]
+pub struct BossCleared {
+    pub words: usize,
+}
+
+/// A completed word was sent into a recycling bin
+#[derive(Event, Clone, Debug)]
+pub struct TrashSorted {
+    pub player: Player,
+    pub material: Material,
+    pub bin: Material,
+}
+
+/// An active trash hit the floor or the pile and lost its word
+#[derive(Event, Clone, Debug)]
+pub struct TrashLanded {
+    /// Who the trash belonged to
+    pub player: Player,
+    /// Whether the word was being typed
+    pub marked: bool,
+}
+
+/// A power up went off, its effect is up to the systems of `PowerUpsPlugin` and `ExplosionPlugin`
+#[derive(Event, Clone, Debug)]
+pub struct PowerUpTriggered {
+    pub power_up: PowerUp,
+    /// The trash it came from
+    pub trash: Entity,
+    pub position: Vec2,
+}
+
+/// An activated explosion trash went off
+#[derive(Event, Clone, Debug)]
+pub struct TrashExploded {
+    pub position: Vec2,
+}
+
+/// A trash caught in an explosion
+#[derive(Event, Clone, Debug)]
+pub struct TrashDestroyed {
+    /// Whether the word was being typed
+    pub marked: bool,
+    /// How many trash the explosion destroyed so far, this one included
+    pub blast_count: usize,
+}
+
+#[derive(Event, Clone, Debug)]
+pub struct ComboLevelUp {
+    pub level: usize,
+}
+
+#[derive(Event, Clone, Debug)]
+pub struct RunEnded;
@@ -0,0 +1,287 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use bevy_rapier2d::prelude::*;
+
+use crate::{GameState, PauseState};
+use crate::events::{PowerUpTriggered, TrashDestroyed, TrashExploded};
+use crate::power_ups::PowerUp;
+use crate::trash::{Trash, TrashActionActive, TrashMarked, handle_trash_collision};
+
+pub struct ExplosionPlugin;
+
+const PARTICLE_COUNT: usize = 24;
+const PARTICLE_SIZE: f32 = 6.0;
+const PARTICLE_GRAVITY: f32 = 400.0;
+const PARTICLE_COLOR: Color = Color::rgb(1.0, 0.6, 0.1);
+const BLAST_COLOR: Color = Color::rgb(1.0, 0.3, 0.0);
+
+
+/// Tweak to change how explosions feel
+#[derive(Resource, Clone, Debug)]
+pub struct ExplosionConfig {
+    /// Everything the blast overlaps is destroyed
+    pub radius: f32,
+    /// Seconds the blast stays, trash falling into it meanwhile is destroyed too
+    pub duration: f32,
+    /// The pile within this distance is pushed outward, harder the closer it is
+    pub push_radius: f32,
+    /// In pixels per second, next to the blast
+    pub push_speed: f32,
+    /// How far the camera moves, in pixels
+    pub shake_strength: f32,
+    /// Seconds the particles live
+    pub particle_lifetime: f32,
+}
+
+/// A blast of `PowerUp::Explosion`, counting the trash it destroyed for the bonus
+#[derive(Component)]
+pub struct Explosion {
+    timer: Timer,
+    destroyed: usize,
+}
+
+/// Pushed by an explosion, the velocity isn't clamped until the timer is done
+#[derive(Component)]
+pub struct Blasted(Timer);
+
+#[derive(Component)]
+struct Particle {
+    velocity: Vec2,
+    lifetime: Timer,
+}
+
+/// The camera is moved around its `origin` until the timer is done
+#[derive(Resource, Default)]
+struct CameraShake {
+    timer: Timer,
+    strength: f32,
+    origin: Option<Vec3>,
+}
+
+
+impl Default for ExplosionConfig {
+    fn default() -> Self {
+        Self {
+            radius: 70.0,
+            duration: 0.3,
+            push_radius: 160.0,
+            push_speed: 300.0,
+            shake_strength: 8.0,
+            particle_lifetime: 0.6,
+        }
+    }
+}
+
+impl Plugin for ExplosionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ExplosionConfig>()
+        .init_resource::<CameraShake>()
+        .add_systems(OnExit(GameState::Playing), stop_camera_shake)
+        .add_systems(Update, (
+                (
+                    explode,
+                    blast,
+                    end_explosions,
+                    end_blasted,
+                    move_particles,
+                    shake_camera,
+                ).chain().after(handle_trash_collision)
+                    .run_if(in_state(GameState::Playing)).run_if(in_state(PauseState::Running)),
+                draw_explosions.run_if(in_state(GameState::Playing)),
+            ));
+    }
+}
+
+
+#[allow(clippy::type_complexity)]
+fn explode(
+    mut commands: Commands,
+    config: Res<ExplosionConfig>,
+    mut camera_shake: ResMut<CameraShake>,
+    mut power_up_triggered_events: EventReader<PowerUpTriggered>,
+    mut exploded_events: EventWriter<TrashExploded>,
+    mut pile_query: Query<(Entity, &Transform, &mut Velocity), (With<Trash>, Without<TrashActionActive>)>,
+) {
+    // A trash touching several things at once goes off once
+    let mut exploded = HashSet::new();
+
+    for triggered in power_up_triggered_events.read() {
+        if triggered.power_up != PowerUp::Explosion || !exploded.insert(triggered.trash) {
+            continue;
+        }
+
+        // The trash that went off isn't part of the bonus
+        if let Some(entity) = commands.get_entity(triggered.trash) {
+            entity.despawn_recursive();
+        }
+
+        commands.spawn((
+            TransformBundle::from_transform(Transform::from_translation(triggered.position.extend(0.0))),
+            Collider::ball(config.radius),
+            Sensor,
+            Explosion {
+                timer: Timer::from_seconds(config.duration, TimerMode::Once),
+                destroyed: 0,
+            },
+        ));
+
+        for (entity, transform, mut velocity) in pile_query.iter_mut() {
+            let offset = transform.translation.truncate() - triggered.position;
+            let distance = offset.length();
+            if distance >= config.push_radius {
+                continue;
+            }
+
+            let direction = offset.try_normalize().unwrap_or(Vec2::Y);
+            velocity.linvel += direction * config.push_speed * (1.0 - distance / config.push_radius);
+            commands.entity(entity).insert(Blasted(Timer::from_seconds(config.duration, TimerMode::Once)));
+        }
+
+        spawn_particles(&mut commands, triggered.position, &config);
+
+        camera_shake.timer = Timer::from_seconds(config.duration, TimerMode::Once);
+        camera_shake.strength = config.shake_strength;
+
+        exploded_events.send(TrashExploded {
+            position: triggered.position,
+        });
+    }
+}
+
+fn spawn_particles(commands: &mut Commands, position: Vec2, config: &ExplosionConfig) {
+    // Evenly spread with a few speeds, the burst doesn't take from the run's random numbers
+    for index in 0..PARTICLE_COUNT {
+        let angle = TAU * index as f32 / PARTICLE_COUNT as f32;
+        let speed = 120.0 + 80.0 * (index % 3) as f32;
+
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: PARTICLE_COLOR,
+                    custom_size: Some(Vec2::splat(PARTICLE_SIZE)),
+                    ..default()
+                },
+                transform: Transform::from_translation(position.extend(1.0)),
+                ..default()
+            },
+            Particle {
+                velocity: Vec2::from_angle(angle) * speed,
+                lifetime: Timer::from_seconds(config.particle_lifetime, TimerMode::Once),
+            },
+        ));
+    }
+}
+
+/// Destroys the trash overlapping a blast, for as long as it lasts
+fn blast(
+    mut commands: Commands,
+    rapier_context: Res<RapierContext>,
+    mut explosion_query: Query<(Entity, &mut Explosion)>,
+    trash_query: Query<Has<TrashMarked>, With<Trash>>,
+    mut destroyed_events: EventWriter<TrashDestroyed>,
+) {
+    let mut destroyed = HashSet::new();
+
+    for (explosion_entity, mut explosion) in explosion_query.iter_mut() {
+        for (entity1, entity2, intersecting) in rapier_context.intersections_with(explosion_entity) {
+            let other = if entity1 == explosion_entity { entity2 } else { entity1 };
+            let Ok(marked) = trash_query.get(other) else {
+                continue;
+            };
+            if !intersecting || !destroyed.insert(other) {
+                continue;
+            }
+
+            explosion.destroyed += 1;
+            destroyed_events.send(TrashDestroyed {
+                marked,
+                blast_count: explosion.destroyed,
+            });
+            commands.entity(other).despawn_recursive();
+        }
+    }
+}
+
+fn end_explosions(
+    mut commands: Commands,
+    mut explosion_query: Query<(Entity, &mut Explosion)>,
+    time: Res<Time>,
+) {
+    for (entity, mut explosion) in explosion_query.iter_mut() {
+        if explosion.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn end_blasted(
+    mut commands: Commands,
+    mut blasted_query: Query<(Entity, &mut Blasted)>,
+    time: Res<Time>,
+) {
+    for (entity, mut blasted) in blasted_query.iter_mut() {
+        if blasted.0.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<Blasted>();
+        }
+    }
+}
+
+fn move_particles(
+    mut commands: Commands,
+    mut particle_query: Query<(Entity, &mut Particle, &mut Transform, &mut Sprite)>,
+    time: Res<Time>,
+) {
+    for (entity, mut particle, mut transform, mut sprite) in particle_query.iter_mut() {
+        if particle.lifetime.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        particle.velocity.y -= PARTICLE_GRAVITY * time.delta_seconds();
+        transform.translation += (particle.velocity * time.delta_seconds()).extend(0.0);
+        sprite.color.set_a(particle.lifetime.percent_left());
+    }
+}
+
+fn shake_camera(
+    mut camera_shake: ResMut<CameraShake>,
+    mut camera_query: Query<&mut Transform, With<Camera>>,
+    time: Res<Time>,
+) {
+    if camera_shake.strength == 0.0 || camera_shake.timer.finished() {
+        stop_camera_shake(camera_shake, camera_query);
+        return;
+    }
+
+    let Ok(mut transform) = camera_query.get_single_mut() else {
+        return;
+    };
+    let origin = *camera_shake.origin.get_or_insert(transform.translation);
+
+    let elapsed = camera_shake.timer.tick(time.delta()).elapsed_secs();
+    let strength = camera_shake.strength * camera_shake.timer.percent_left();
+    let offset = Vec2::new((elapsed * 73.0).sin(), (elapsed * 91.0).cos()) * strength;
+    transform.translation = origin + offset.extend(0.0);
+}
+
+/// Puts the camera back where it was before the shake
+fn stop_camera_shake(
+    mut camera_shake: ResMut<CameraShake>,
+    mut camera_query: Query<&mut Transform, With<Camera>>,
+) {
+    if let Some(origin) = camera_shake.origin {
+        for mut transform in camera_query.iter_mut() {
+            transform.translation = origin;
+        }
+    }
+    *camera_shake = CameraShake::default();
+}
+
+fn draw_explosions(mut gizmos: Gizmos, explosion_query: Query<(&Transform, &Explosion)>, config: Res<ExplosionConfig>) {
+    for (transform, explosion) in explosion_query.iter() {
+        let radius = config.radius * (0.5 + 0.5 * explosion.timer.percent());
+        gizmos.circle_2d(transform.translation.truncate(), radius, BLAST_COLOR.with_a(explosion.timer.percent_left()));
+    }
+}
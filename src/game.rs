@@ -1,19 +1,29 @@
-use std::ops::Sub;
+use std::ops::RangeInclusive;
 use std::time::Duration;
 
+use crate::game_mode::{GameMode, has_difficulty_ramp, is_solo};
 use crate::game_over::{GameOver, GameOverLine};
-use crate::loading::TextureAssets;
-use crate::{GameState, typing};
+use crate::catalogue::{TrashCatalogue, TrashKind};
+use crate::difficulty::{DifficultyProfiles, RunDifficulty, adaptive_difficulty, difficulty_preset};
+use crate::events::{CorrectKey, WrongKey};
+use crate::loading::{DataAssets, TextureAssets};
+use crate::{GameState, PauseState, typing};
 use crate::menu::{ButtonColors, ChangeState};
-use crate::score::{Score, ComboMeter, ComboModifier};
-use crate::trash::{TrashType, PowerUp, BufferText, TrashActionDuplicate, TrashActionActive, TrashBundle, get_trash_sprite, TrashMarked, Trash};
-use crate::trash_text::{TrashText, TrashTextBundle, highlight_characters, remove_highlight};
-use crate::typing::TypingBuffer;
+use crate::phrases::choose_phrase;
+use crate::seed::{RunRng, RunSeed, seed_run};
+use crate::settings::{MAX_WORD_LENGTH, Settings};
+use crate::sorting::{sorted_into, sorting_bins};
+use crate::target_lock::{TargetLock, lock_target};
+use crate::power_ups::PowerUp;
+use crate::trash::{Boss, BufferText, TrashActionDuplicate, TrashActionActive, TrashBundle, TrashMarked, Trash, LinkedTrash, spawn_boss_progress};
+use crate::trash_text::{TrashText, TrashTextBundle, highlight_characters, line_breaks, remove_highlight};
+use crate::typing::{PerPlayer, Player, TypingBuffer, owner};
+use crate::versus::{VERSUS_FIELD_HALF_WIDTH, field_center_x};
+use crate::words::{AvailableWords, WordStyle, is_typed_with};
+use bevy::ecs::system::EntityCommands;
 use bevy::prelude::*;
 use bevy::sprite::Anchor;
 use bevy::transform::TransformSystem;
-use bevy::utils::HashMap;
-use bevy_progressbar::ProgressBar;
 use bevy_rapier2d::prelude::*;
 use rand::Rng;
 
@@ -27,17 +37,33 @@ pub struct Wall;
 #[derive(Component, Default)]
 pub struct Floor;
 
-#[derive(Resource)]
+// Both timers get their durations from the difficulty profile when the run starts
+#[derive(Resource, Default)]
 pub struct TrashSpawnTimer(pub Timer);
 
-#[derive(Resource)]
+#[derive(Resource, Default)]
 struct DifficultyTimer(pub Timer);
 
+#[derive(Resource, Default)]
+struct BossSpawnTimer(Timer);
+
+/// How many times the difficulty went up since the start of the run
+#[derive(Resource, Default)]
+pub struct DifficultyLevel(pub u32);
+
+/// The keys typed during the current difficulty level, for the adaptive difficulty
+#[derive(Resource, Default)]
+struct LevelAccuracy {
+    correct: usize,
+    wrong: usize,
+}
+
 #[derive(Resource)]
 struct BufferTextDeleteTimer(Timer);
 
-#[derive(Resource)]
-pub struct AvailableWords(HashMap<String, Vec<String>>);
+/// Where the last trash spawned, the next one is kept away from it
+#[derive(Resource, Default)]
+pub struct PreviousSpawnPosition(f32);
 
 
 pub const WINDOW_WIDTH: f32 = 700.0;
@@ -51,21 +77,35 @@ const TRASH_MAXIMUM_VERTICAL_VELOCITY_LENGTH: f32 = 40.0;
 const TRASH_MAXIMUM_HORIZONTAL_VELOCITY_LENGTH: f32 = 600.0;
 const TRASH_SPAWN_DISTANCE_BETWEEN_SPAWNS: f32 = 30.0;
 
-const INITIAL_TRASH_SPAWN_RATE: f32 = 2.0;
-const INITIAL_DIFICULTY_INCREASE_RATE: f32 = 10.0;
+const LINK_GROUP_SIZE: usize = 3;
+const LINK_GROUP_SPACING: f32 = 110.0;
+
+// Bosses fall at a fraction of the speed of the other trash, and are slowed down as they go
+const BOSS_FALL_SPEED_SCALE: f32 = 0.4;
+const BOSS_DAMPING: f32 = 1.5;
+
+// Picking letters with a stick is a lot slower than typing
+pub const GAMEPAD_TRASH_SPAWN_SPEED: f32 = 0.6;
+// Below this accuracy during a level the adaptive difficulty goes down a level instead of up
+const ADAPTIVE_ACCURACY_THRESHOLD: f32 = 0.85;
 
 
 impl Plugin for PlayPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(DifficultyTimer(Timer::from_seconds(INITIAL_DIFICULTY_INCREASE_RATE, TimerMode::Repeating)))
-        .insert_resource(TrashSpawnTimer(Timer::from_seconds(INITIAL_TRASH_SPAWN_RATE, TimerMode::Repeating)))
-        .insert_resource(AvailableWords(get_available_words_from_file()))
-        .add_systems(OnEnter(GameState::Playing), setup)
+        app.init_resource::<DifficultyTimer>()
+        .init_resource::<TrashSpawnTimer>()
+        .init_resource::<BossSpawnTimer>()
+        .init_resource::<DifficultyLevel>()
+        .init_resource::<LevelAccuracy>()
+        .init_resource::<RunDifficulty>()
+        .init_resource::<PreviousSpawnPosition>()
+        .add_systems(OnEnter(GameState::Playing), setup.after(seed_run))
         .add_systems(Update, (
-                spawn_trash.run_if(in_state(GameState::Playing)),
+                spawn_trash.run_if(in_state(GameState::Playing)).run_if(in_state(PauseState::Running)).run_if(is_solo),
+                spawn_boss.after(spawn_trash).run_if(in_state(GameState::Playing)).run_if(in_state(PauseState::Running)).run_if(is_solo),
                 // update_trash.after(update_on_wrong_letter).run_if(in_state(GameState::Playing)),
-                update_difficuly.after(setup).run_if(in_state(GameState::Playing)),
-                update_on_wrong_letter.after(typing::typing),
+                update_difficuly.after(setup).run_if(in_state(GameState::Playing)).run_if(in_state(PauseState::Running)).run_if(has_difficulty_ramp),
+                update_on_wrong_letter.after(typing::typing).after(lock_target),
                 update_buffer_text.after(typing::typing),
                 clean_typing_buffer.after(update_on_wrong_letter),
             ))
@@ -82,18 +122,39 @@ impl Plugin for PlayPlugin {
 }
 
 
+#[allow(clippy::too_many_arguments)]
 fn setup(
     mut commands: Commands,
     textures: Res<TextureAssets>,
-    window: Query<&Window>,
+    data_assets: Res<DataAssets>,
+    difficulty_profiles: Res<Assets<DifficultyProfiles>>,
     mut trash_spawn_timer: ResMut<TrashSpawnTimer>,
     mut difficulty_timer: ResMut<DifficultyTimer>,
+    mut boss_spawn_timer: ResMut<BossSpawnTimer>,
+    mut difficulty_level: ResMut<DifficultyLevel>,
+    mut level_accuracy: ResMut<LevelAccuracy>,
+    mut run_difficulty: ResMut<RunDifficulty>,
+    mut previous_spawn_position: ResMut<PreviousSpawnPosition>,
+    settings: Res<Settings>,
+    run_seed: Res<RunSeed>,
+    game_mode: Res<GameMode>,
     // typing_buffer: Res<TypingBuffer>,
 ) {
-    trash_spawn_timer.0.reset();
-    difficulty_timer.0.reset();
-    trash_spawn_timer.0.set_duration(Duration::from_secs(INITIAL_TRASH_SPAWN_RATE as u64));
-    difficulty_timer.0.set_duration(Duration::from_secs(INITIAL_DIFICULTY_INCREASE_RATE as u64));
+    previous_spawn_position.0 = 0.0;
+    *level_accuracy = LevelAccuracy::default();
+    let profile = difficulty_profiles.get(&data_assets.difficulty_profiles)
+        .expect("the difficulty profiles are loaded before leaving GameState::Loading")
+        .get(difficulty_preset(&settings, &run_seed))
+        .clone();
+    difficulty_level.0 = starting_difficulty(&settings, &run_seed);
+    // Starting at a higher difficulty skips the levels below it
+    trash_spawn_timer.0 = Timer::from_seconds(profile.at(difficulty_level.0).spawn_interval, TimerMode::Repeating);
+    difficulty_timer.0 = Timer::from_seconds(profile.level_duration, TimerMode::Repeating);
+    boss_spawn_timer.0 = Timer::from_seconds(profile.boss_interval, TimerMode::Repeating);
+    *run_difficulty = RunDifficulty {
+        profile,
+        adaptive: adaptive_difficulty(&settings, &run_seed),
+    };
 
     // commands.spawn((
     //     TextBundle::from_section(
@@ -126,44 +187,68 @@ fn setup(
     // });
 
 
-    let window = window.single();
     // let max_x: f32 = window.width() / 2.0;
     // let max_y = window.height() / 2.0;
     let max_x: f32 = WINDOW_WIDTH / 2.0;
     let max_y = WINDOW_HEIGHT / 2.0;
 
-    create_borders(&mut commands, &textures, max_x, max_y);
+    // Versus splits the window into a playfield per side
+    if *game_mode == GameMode::Versus {
+        for player in Player::BOTH {
+            let line = create_playfield(&mut commands, &textures, field_center_x(player), VERSUS_FIELD_HALF_WIDTH, max_y, false);
+            commands.entity(line).insert(player);
+        }
+    } else {
+        // Zen runs have no line, it's only there to know when to sweep the pile
+        create_playfield(&mut commands, &textures, 0.0, max_x, max_y, *game_mode == GameMode::Zen);
+    }
 
     commands.spawn(
         SpriteBundle {
             sprite: Sprite {
-                color: Color::RED,
-                custom_size: Some(Vec2::new(WINDOW_WIDTH - 85.0, 10.0)),
+                custom_size: Some(Vec2::new(WINDOW_WIDTH, WINDOW_HEIGHT)),
                 ..default()
             },
-            transform: Transform::from_translation(Vec3::new(0.0, WINDOW_HEIGHT - 390.0, 0.0)),
+            texture: textures.background.clone(),
+            transform: Transform::from_translation(Vec3::new(0.0, max_y, -4.0)),
             ..default()
         }
-    )
-    .insert(Collider::cuboid(window.width() - 85.0, 10.0))
-    .insert(Sensor)
-    .insert(GameOverLine);
+    );
+}
+
+
+/// The walls and the floor around `center_x`, with the line the pile must not reach. Returns the line.
+fn create_playfield(
+    commands: &mut Commands,
+    textures: &Res<TextureAssets>,
+    center_x: f32,
+    max_x: f32,
+    max_y: f32,
+    hidden_line: bool,
+) -> Entity {
+    create_borders(commands, textures, center_x, max_x, max_y);
 
+    let line_width = max_x * 2.0 - 85.0;
     commands.spawn(
         SpriteBundle {
             sprite: Sprite {
-                custom_size: Some(Vec2::new(WINDOW_WIDTH, WINDOW_HEIGHT)),
+                color: Color::RED,
+                custom_size: Some(Vec2::new(line_width, 10.0)),
                 ..default()
             },
-            texture: textures.background.clone(),
-            transform: Transform::from_translation(Vec3::new(0.0, max_y, -4.0)),
+            transform: Transform::from_translation(Vec3::new(center_x, WINDOW_HEIGHT - 390.0, 0.0)),
+            visibility: if hidden_line { Visibility::Hidden } else { Visibility::Inherited },
             ..default()
         }
-    );
+    )
+    // Kept within the walls so a line never touches the pile of the other versus side
+    .insert(Collider::cuboid(line_width / 2.0, 10.0))
+    .insert(Sensor)
+    .insert(GameOverLine)
+    .id()
 }
 
-
-fn create_borders(commands: &mut Commands, textures: &Res<TextureAssets>, max_x: f32, max_y: f32) {
+fn create_borders(commands: &mut Commands, textures: &Res<TextureAssets>, center_x: f32, max_x: f32, max_y: f32) {
     let y_pos = 16.0;
     let x_pos = (max_x * -1.0) + 16.0;
     let iterations_x = (max_x % BORDER_TILE_SIZE) + max_x;
@@ -171,19 +256,19 @@ fn create_borders(commands: &mut Commands, textures: &Res<TextureAssets>, max_x:
 
     for x in (0..=iterations_x as u32).step_by(BORDER_TILE_SIZE as usize) {
         commands.spawn(
-            get_border_tile(Vec3::new(x as f32, y_pos, 1.0), textures.ground.clone(), BORDER_TILE_SCALE.clone())
+            get_border_tile(Vec3::new(center_x + x as f32, y_pos, 1.0), textures.ground.clone(), BORDER_TILE_SCALE.clone())
         ).insert(Collider::cuboid(BORDER_TILE_SIZE / 2.0, BORDER_TILE_SIZE / 2.0))
         .insert(Floor);
 
         commands.spawn(
-            get_border_tile(Vec3::new(x as f32 * -1.0, y_pos, 1.0), textures.ground.clone(), BORDER_TILE_SCALE.clone())
+            get_border_tile(Vec3::new(center_x - x as f32, y_pos, 1.0), textures.ground.clone(), BORDER_TILE_SCALE.clone())
         ).insert(Collider::cuboid(BORDER_TILE_SIZE / 2.0, BORDER_TILE_SIZE / 2.0))
         .insert(Floor);
     }
 
     for y in (16..=iterations_y as u32).step_by(BORDER_TILE_SIZE as usize) {
         commands.spawn(
-            get_border_tile(Vec3::new(x_pos, y as f32, 0.0), textures.wall.clone(), BORDER_TILE_SCALE.clone())
+            get_border_tile(Vec3::new(center_x + x_pos, y as f32, 0.0), textures.wall.clone(), BORDER_TILE_SCALE.clone())
         )
         .insert(Collider::cuboid(BORDER_TILE_SIZE / 2.0, BORDER_TILE_SIZE / 2.0))
         .insert(Wall);
@@ -205,7 +290,7 @@ fn create_borders(commands: &mut Commands, textures: &Res<TextureAssets>, max_x:
         // });
 
         commands.spawn(
-            get_border_tile(Vec3::new(-x_pos, y as f32, 0.0), textures.wall.clone(), BORDER_TILE_SCALE.clone())
+            get_border_tile(Vec3::new(center_x - x_pos, y as f32, 0.0), textures.wall.clone(), BORDER_TILE_SCALE.clone())
         )
         .insert(Collider::cuboid(BORDER_TILE_SIZE / 2.0, BORDER_TILE_SIZE / 2.0))
         .insert(Wall);
@@ -224,8 +309,7 @@ fn get_border_tile(position: Vec3, texture: Handle<Image>, texture_scale: Vec2)
     }
 }
 
-fn get_random_coordinate(border: f32, previous: f32) -> f32 {
-    let mut random = rand::thread_rng();
+pub fn get_random_coordinate(random: &mut impl Rng, border: f32, previous: f32) -> f32 {
     // let max_x: f32 = window.width() / 2.0;
     // let y_pos = (window.height() / 2.0) * 2.0;
 
@@ -238,105 +322,224 @@ fn get_random_coordinate(border: f32, previous: f32) -> f32 {
     return random_x;
 }
 
+/// Returns the x of the left most trash in a linked group so the whole group fits between the walls
+fn get_random_link_group_coordinate(random: &mut impl Rng, border: f32) -> f32 {
+    let group_width = LINK_GROUP_SPACING * (LINK_GROUP_SIZE - 1) as f32;
+
+    random.gen_range(-border + BORDER_TILE_SIZE * 2.0 .. border - BORDER_TILE_SIZE * 2.0 - group_width)
+}
 
+
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_trash(
     mut commands: Commands,
-    textures: Res<TextureAssets>,
+    data_assets: Res<DataAssets>,
+    catalogues: Res<Assets<TrashCatalogue>>,
     // keyboard_input: Res<Input<KeyCode>>,
     // window: Query<&Window>,
     time: Res<Time>,
     available_words: Res<AvailableWords>,
+    difficulty_level: Res<DifficultyLevel>,
+    run_difficulty: Res<RunDifficulty>,
+    settings: Res<Settings>,
+    run_seed: Res<RunSeed>,
+    game_mode: Res<GameMode>,
+    mut run_rng: ResMut<RunRng>,
+    words_on_screen: Query<&TrashText>,
+    bosses: Query<&Boss>,
     mut spawn_timer: ResMut<TrashSpawnTimer>,
-    mut previous_spawn_position: Local<f32>,
+    mut previous_spawn_position: ResMut<PreviousSpawnPosition>,
+    mut link_group_counter: Local<u32>,
 ) {
+    let spawn_speed = if slow_spawns(&settings, &run_seed) { GAMEPAD_TRASH_SPAWN_SPEED } else { 1.0 };
+    let difficulty = run_difficulty.profile.at(difficulty_level.0);
+    if spawn_timer.0.tick(time.delta().mul_f32(spawn_speed)).just_finished() {
+        if words_on_screen.iter().count() >= difficulty.max_trash {
+            return;
+        }
 
-    static SPAWN_CHANCES: [TrashType; 9] = [
-        TrashType::Bottle,
-        TrashType::Pizza,
-        TrashType::BigBox,
-        TrashType::GlassBottle,
-        TrashType::News,
-        TrashType::Shampoo,
-        TrashType::SmallCan,
-        TrashType::Soda,
-        TrashType::Spray,
-    ];
-    static POWER_UP_CHANCES: [PowerUp; 10] = [
-        PowerUp::None,
-        PowerUp::None,
-        PowerUp::None,
-        PowerUp::None,
-        PowerUp::None,
-        PowerUp::None,
-        PowerUp::None,
-        PowerUp::None,
-        PowerUp::None,
-        PowerUp::Explosion,
-    ];
-
-    if spawn_timer.0.tick(time.delta()).just_finished() {
         // let window = window.single();
+        let catalogue = catalogues.get(&data_assets.trash_catalogue).expect("the catalogue is loaded before leaving GameState::Loading");
 
-        let mut random = rand::thread_rng();
+        let RunRng { trash: random, words: words_random } = &mut *run_rng;
         // let max_x: f32 = window.width() / 2.0;
         // let y_pos = (window.height() / 2.0) * 2.0;
         let max_x: f32 = WINDOW_WIDTH / 2.0;
         let y_pos = WINDOW_HEIGHT;
 
-        let random_x = get_random_coordinate(max_x, *previous_spawn_position);
-        *previous_spawn_position = random_x;
-        let trash_type: TrashType = SPAWN_CHANCES[random.gen_range(0..SPAWN_CHANCES.len())].clone();
-        let power_up: PowerUp = POWER_UP_CHANCES[random.gen_range(0..POWER_UP_CHANCES.len())].clone();
-
-        // TODO: make sure the same word doesn't appear twice in a row
-        // A solution might be to have search to search for a word as long as it's not in a list of
-        // already used words which we can get from a query
-        // Also we need to be able to to limit the amount of letters in a word
-        let mut trash = Trash::get_by_type(trash_type);
-        if power_up != PowerUp::None {
-            trash.power_up = power_up;
+        let power_up = if random.gen_bool(difficulty.power_up_chance as f64) {
+            PowerUp::choose(random)
+        } else {
+            PowerUp::None
+        };
+        let word_difficulty = difficulty.word_difficulty;
+        let fall_velocity = Velocity::linear(Vec2::new(0.0, -difficulty.fall_speed));
+        let mut taken_words = taken_words(&words_on_screen, &bosses);
+
+        if power_up == PowerUp::Link {
+            *link_group_counter += 1;
+            let group_x = get_random_link_group_coordinate(random, max_x);
+            previous_spawn_position.0 = group_x + LINK_GROUP_SPACING * (LINK_GROUP_SIZE - 1) as f32;
+
+            for i in 0..LINK_GROUP_SIZE {
+                let kind = catalogue.choose(random).clone();
+                let position = Vec3::new(group_x + LINK_GROUP_SPACING * i as f32, y_pos, 0.0);
+                let Some(word) = choose_word(&available_words, &settings, &run_seed, &game_mode, words_random, word_difficulty, &mut taken_words, None) else {
+                    continue;
+                };
+                spawn_single_trash(&mut commands, &settings, word, kind, PowerUp::Link, position)
+                    .insert(LinkedTrash(*link_group_counter))
+                    .insert(fall_velocity);
+            }
+            return;
         }
 
-        let trash_bundle = TrashBundle::new(get_trash_sprite(&trash.trash_type, &textures), trash);
-        let trash_text = TrashBundle::create_text(
-            get_random_word(&available_words),
-            Anchor::Custom(Vec2::new(0.0, -2.0)),
-            Color::GREEN,
-            TextStyle {
-                color: Color::WHITE,
-                font_size: 30.0,
-                ..default()
-            }
-        );
-        commands.spawn(trash_bundle)
-            .insert(Transform::from_translation(Vec3::new(random_x as f32, y_pos, 0.0)))
-            .insert(TrashActionActive)
-            .insert(TrashActionDuplicate)
-            .with_children(|parent| {
-                parent.spawn(trash_text);
-            });
+        let random_x = get_random_coordinate(random, max_x, previous_spawn_position.0);
+        previous_spawn_position.0 = random_x;
+        let kind = catalogue.choose(random).clone();
 
+        let Some(word) = choose_word(&available_words, &settings, &run_seed, &game_mode, words_random, word_difficulty, &mut taken_words, None) else {
+            return;
+        };
+        spawn_single_trash(&mut commands, &settings, word, kind, power_up, Vec3::new(random_x, y_pos, 0.0))
+            .insert(fall_velocity);
     }
 }
 
+/// Every so often a boss falls, it takes a sequence of words to clear
+#[allow(clippy::too_many_arguments)]
+fn spawn_boss(
+    mut commands: Commands,
+    data_assets: Res<DataAssets>,
+    catalogues: Res<Assets<TrashCatalogue>>,
+    time: Res<Time>,
+    available_words: Res<AvailableWords>,
+    difficulty_level: Res<DifficultyLevel>,
+    run_difficulty: Res<RunDifficulty>,
+    settings: Res<Settings>,
+    run_seed: Res<RunSeed>,
+    game_mode: Res<GameMode>,
+    mut run_rng: ResMut<RunRng>,
+    words_on_screen: Query<&TrashText>,
+    bosses: Query<&Boss>,
+    mut boss_spawn_timer: ResMut<BossSpawnTimer>,
+) {
+    let spawn_speed = if slow_spawns(&settings, &run_seed) { GAMEPAD_TRASH_SPAWN_SPEED } else { 1.0 };
+    // One boss at a time
+    if !boss_spawn_timer.0.tick(time.delta().mul_f32(spawn_speed)).just_finished() || !bosses.is_empty() {
+        return;
+    }
+
+    let catalogue = catalogues.get(&data_assets.trash_catalogue).expect("the catalogue is loaded before leaving GameState::Loading");
+    let RunRng { trash: random, words: words_random } = &mut *run_rng;
+    let Some(kind) = catalogue.choose_boss(random).cloned() else {
+        return;
+    };
+
+    let difficulty = run_difficulty.profile.at(difficulty_level.0);
+    // The words of the boss are reserved from the start, so its next word never shares a prefix with the others
+    let mut taken_words = taken_words(&words_on_screen, &bosses);
+    let Some(words) = (0..difficulty.boss_words.max(1))
+        .map(|_| choose_word(&available_words, &settings, &run_seed, &game_mode, words_random, difficulty.word_difficulty, &mut taken_words, None))
+        .collect::<Option<Vec<String>>>()
+    else {
+        return;
+    };
+
+    let border = WINDOW_WIDTH / 2.0 - BORDER_TILE_SIZE * 2.0 - kind.size().x;
+    let x = if border > 0.0 { random.gen_range(-border..border) } else { 0.0 };
+    let boss = Boss::new(words.clone());
+    let half_height = kind.size().y;
+
+    spawn_single_trash(&mut commands, &settings, words[0].clone(), kind, PowerUp::None, Vec3::new(x, WINDOW_HEIGHT, 0.0))
+        .insert(Velocity::linear(Vec2::new(0.0, -difficulty.fall_speed * BOSS_FALL_SPEED_SCALE)))
+        .insert(Damping {
+            linear_damping: BOSS_DAMPING,
+            angular_damping: BOSS_DAMPING,
+        })
+        .with_children(|parent| spawn_boss_progress(parent, &boss, half_height))
+        .insert(boss);
+}
+
+/// The words on screen and the words the bosses still have to show
+fn taken_words(words_on_screen: &Query<&TrashText>, bosses: &Query<&Boss>) -> Vec<String> {
+    words_on_screen.iter()
+        .map(|trash_text| &trash_text.word)
+        .chain(bosses.iter().flat_map(|boss| boss.upcoming()))
+        .cloned()
+        .collect()
+}
 
+
+pub fn spawn_single_trash<'w, 's, 'a>(
+    commands: &'a mut Commands<'w, 's>,
+    settings: &Settings,
+    word: String,
+    kind: TrashKind,
+    power_up: PowerUp,
+    position: Vec3,
+) -> EntityCommands<'w, 's, 'a> {
+    let trash_bundle = TrashBundle::new(Trash::new(kind, power_up));
+    let trash_text = create_word_text(settings, word);
+
+    let mut entity_commands = commands.spawn(trash_bundle);
+    entity_commands
+        .insert(Transform::from_translation(position))
+        .insert(TrashActionActive)
+        .insert(TrashActionDuplicate)
+        .with_children(|parent| {
+            parent.spawn(trash_text);
+        });
+
+    entity_commands
+}
+
+
+/// The word a trash carries, as its child
+pub fn create_word_text(settings: &Settings, word: String) -> TrashTextBundle {
+    // The anchor is relative to the size of the text, this keeps the last line of a wrapped phrase
+    // where a single word would be and stacks the other lines above it
+    let lines = line_breaks(&word).len() as f32 + 1.0;
+    TrashBundle::create_text(
+        word,
+        Anchor::Custom(Vec2::new(0.0, -(1.5 / lines + 0.5))),
+        settings.highlight_palette.highlight_color(),
+        TextStyle {
+            color: Color::WHITE,
+            font_size: 30.0,
+            ..default()
+        }
+    )
+}
+
+
+#[allow(clippy::too_many_arguments)]
 fn update_difficuly(
     mut trash_spawn_timer: ResMut<TrashSpawnTimer>,
     mut difficulty_timer: ResMut<DifficultyTimer>,
+    mut difficulty_level: ResMut<DifficultyLevel>,
+    mut level_accuracy: ResMut<LevelAccuracy>,
+    run_difficulty: Res<RunDifficulty>,
+    mut correct_key_events: EventReader<CorrectKey>,
+    mut wrong_key_events: EventReader<WrongKey>,
     time: Res<Time>,
 ) {
-
+    level_accuracy.correct += correct_key_events.read().count();
+    level_accuracy.wrong += wrong_key_events.read().filter(|wrong_key| wrong_key.letter.is_some()).count();
 
     if difficulty_timer.0.tick(time.delta()).just_finished() {
-        if trash_spawn_timer.0.duration() <= Duration::from_secs_f32(1.0) {
-            return;
+        let typed = level_accuracy.correct + level_accuracy.wrong;
+        let struggling = typed > 0 && (level_accuracy.correct as f32 / typed as f32) < ADAPTIVE_ACCURACY_THRESHOLD;
+        if run_difficulty.adaptive && struggling {
+            difficulty_level.0 = difficulty_level.0.saturating_sub(1);
+        } else {
+            difficulty_level.0 += 1;
         }
+        *level_accuracy = LevelAccuracy::default();
 
-        let duration = trash_spawn_timer.0.duration().sub(Duration::from_secs_f32(0.2));
-        trash_spawn_timer.0.set_duration(duration) ;
-
+        let spawn_interval = run_difficulty.profile.at(difficulty_level.0).spawn_interval;
+        trash_spawn_timer.0.set_duration(Duration::from_secs_f32(spawn_interval));
     }
-
 }
 
 fn update_buffer_text(
@@ -344,7 +547,7 @@ fn update_buffer_text(
     mut query: Query<&mut Text, With<BufferText>>,
 ) {
     for mut text in &mut query {
-        text.sections[0].value = typing_buffer.0.clone();
+        text.sections[0].value = typing_buffer.0[Player::One].clone();
     }
 }
 
@@ -353,37 +556,48 @@ pub fn clean_typing_buffer(
     mut typing_buffer: ResMut<TypingBuffer>,
     marked_trash_query: Query<Entity, With<TrashMarked>>,
     trash_query: Query<(&Parent, &TrashText)>,
-    mut previous_typing_buffer: Local<String>,
+    player_query: Query<&Player>,
+    game_mode: Res<GameMode>,
+    mut previous_typing_buffers: Local<PerPlayer<String>>,
 
 ) {
 
-    let mut is_word_exists = false;
-    if marked_trash_query.is_empty() {
-        for (_, trash_text) in trash_query.iter() {
-            if trash_text.word.starts_with(&*previous_typing_buffer) {
-                is_word_exists = true;
-                break;
+    for player in Player::playing(&game_mode) {
+        let previous_typing_buffer = &mut previous_typing_buffers[*player];
+        let mut is_word_exists = false;
+        if !marked_trash_query.iter().any(|trash| owner(&player_query, trash) == *player) {
+            for (parent, trash_text) in trash_query.iter() {
+                if owner(&player_query, parent.get()) == *player && trash_text.word.starts_with(&*previous_typing_buffer) {
+                    is_word_exists = true;
+                    break;
+                }
+            }
+            if !is_word_exists {
+                typing_buffer.0[*player] = "".to_string();
             }
         }
-        if !is_word_exists {
-            typing_buffer.0 = "".to_string();
-        }
-    }
 
-    if *previous_typing_buffer != typing_buffer.0 && !is_word_exists {
-        *previous_typing_buffer = typing_buffer.0.clone();
+        if *previous_typing_buffer != typing_buffer.0[*player] && !is_word_exists {
+            *previous_typing_buffer = typing_buffer.0[*player].clone();
+        }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn update_on_wrong_letter(
     mut commands: Commands,
     mut typing_buffer: ResMut<TypingBuffer>,
-    mut combo_meter_query: Query<&mut ProgressBar, With<ComboMeter>>,
-    mut combo_modifier: ResMut<ComboModifier>,
     // keyboard_input: Res<Input<KeyCode>>,
     trash_query: Query<(&Parent, &TrashText)>,
     marked_trash_query: Query<Entity, With<TrashMarked>>,
-    mut previous_typing_buffer: Local<String>,
+    player_query: Query<&Player>,
+    target_lock: Res<TargetLock>,
+    settings: Res<Settings>,
+    run_seed: Res<RunSeed>,
+    game_mode: Res<GameMode>,
+    mut correct_key_events: EventWriter<CorrectKey>,
+    mut wrong_key_events: EventWriter<WrongKey>,
+    mut previous_typing_buffers: Local<PerPlayer<String>>,
 
 ) {
 
@@ -391,42 +605,63 @@ pub fn update_on_wrong_letter(
         return;
     }
 
-    let mut to_be_removed = vec![];
-    let mut is_existing_matching_word = false;
+    // A completed word followed by a bin key is the trash being sorted
+    let sorting = sorting_bins(&settings, &run_seed);
 
-    for (parent, trash_text) in trash_query.iter() {
-        if trash_text.word.starts_with(&typing_buffer.0) {
-            if marked_trash_query.get(parent.get()).is_err() {
-                commands.entity(parent.get()).insert(TrashMarked);
-            }
-            is_existing_matching_word = true;
+    for player in Player::playing(&game_mode) {
+        let player = *player;
+        let previous_typing_buffer = &mut previous_typing_buffers[player];
+        // The keys of one player don't touch the words of the other
+        if typing_buffer.0[player] == *previous_typing_buffer && !typing_buffer.0[player].is_empty() {
+            continue;
+        }
 
-        } else {
-            if marked_trash_query.get(parent.get()).is_ok() {
-                to_be_removed.push(parent.get());
+        let mut to_be_removed = vec![];
+        let mut is_existing_matching_word = false;
+
+        for (parent, trash_text) in trash_query.iter() {
+            if owner(&player_query, parent.get()) != player {
+                continue;
             }
-        }
-    }
 
-    if !is_existing_matching_word {
-        for mut progress_bar in &mut combo_meter_query.iter_mut() {
-            progress_bar.reset();
-            combo_modifier.0 = 1;
+            let matches = trash_text.word.starts_with(&typing_buffer.0[player])
+                || (sorting && sorted_into(&trash_text.word, &typing_buffer.0[player]).is_some());
+            if target_lock.allows(parent.get()) && matches {
+                if marked_trash_query.get(parent.get()).is_err() {
+                    commands.entity(parent.get()).insert(TrashMarked);
+                }
+                is_existing_matching_word = true;
+
+            } else {
+                if marked_trash_query.get(parent.get()).is_ok() {
+                    to_be_removed.push(parent.get());
+                }
+            }
         }
 
-        typing_buffer.0 = previous_typing_buffer.clone();
+        if !is_existing_matching_word {
+            wrong_key_events.send(WrongKey {
+                player,
+                letter: typing_buffer.0[player].chars().last(),
+            });
+            typing_buffer.0[player] = previous_typing_buffer.clone();
 
-    } else {
-        *previous_typing_buffer = typing_buffer.0.clone();
-    }
+        } else {
+            if typing_buffer.0[player].chars().count() > previous_typing_buffer.chars().count() {
+                correct_key_events.send(CorrectKey { player });
+            }
+            *previous_typing_buffer = typing_buffer.0[player].clone();
+        }
 
 
-    for entity in to_be_removed {
-        commands.entity(entity).remove::<TrashMarked>();
+        for entity in to_be_removed {
+            commands.entity(entity).remove::<TrashMarked>();
+        }
     }
 }
 
 
+#[allow(clippy::type_complexity)]
 fn delete_all_play_entities(
     mut commands: Commands,
     query: Query<Entity, (Without<GameOver>, Without<Camera>, Without<Window>)>,
@@ -437,59 +672,51 @@ fn delete_all_play_entities(
 }
 
 
-fn get_random_word(available_words: &Res<AvailableWords>) -> String {
-    let mut random = rand::thread_rng();
-    let mut random_words: Vec<String> = Vec::new();
+// Daily runs ignore the personal difficulty settings so everyone plays the same run
+pub fn starting_difficulty(settings: &Settings, run_seed: &RunSeed) -> u32 {
+    if run_seed.is_daily() { 0 } else { settings.starting_difficulty }
+}
 
-    while random_words.len() == 0 {
-        let random_letter = random.gen_range('a' .. 'z').to_string();
-random_words = available_words.0.get(random_letter.as_str()).unwrap().clone();
+pub fn word_length_limits(settings: &Settings, run_seed: &RunSeed) -> RangeInclusive<usize> {
+    if run_seed.is_daily() {
+        1..=MAX_WORD_LENGTH
+    } else {
+        settings.min_word_length..=settings.max_word_length
     }
-
-    random_words[random.gen_range(0..random_words.len())].clone()
 }
 
+pub fn word_style(settings: &Settings, run_seed: &RunSeed) -> WordStyle {
+    if run_seed.is_daily() { WordStyle::default() } else { settings.word_style }
+}
 
-fn get_available_words_from_file() -> HashMap<String, Vec<String>> {
-    let words: Vec<&str> = include_str!("../assets/no-swear.txt").split("\n").collect();
-
-    let mut serialized_words: HashMap<String, Vec<String>>  = HashMap::from([
-        ("a".to_string(), Vec::new()),
-        ("b".to_string(), Vec::new()),
-        ("c".to_string(), Vec::new()),
-        ("d".to_string(), Vec::new()),
-        ("e".to_string(), Vec::new()),
-        ("f".to_string(), Vec::new()),
-        ("g".to_string(), Vec::new()),
-        ("h".to_string(), Vec::new()),
-        ("i".to_string(), Vec::new()),
-        ("j".to_string(), Vec::new()),
-        ("k".to_string(), Vec::new()),
-        ("l".to_string(), Vec::new()),
-        ("m".to_string(), Vec::new()),
-        ("n".to_string(), Vec::new()),
-        ("o".to_string(), Vec::new()),
-        ("p".to_string(), Vec::new()),
-        ("q".to_string(), Vec::new()),
-        ("r".to_string(), Vec::new()),
-        ("s".to_string(), Vec::new()),
-        ("t".to_string(), Vec::new()),
-        ("u".to_string(), Vec::new()),
-        ("v".to_string(), Vec::new()),
-        ("w".to_string(), Vec::new()),
-        ("x".to_string(), Vec::new()),
-        ("y".to_string(), Vec::new()),
-        ("z".to_string(), Vec::new()),
-    ]);
-
-    for word in words {
-        let first_letter = match word.chars().next() {
-            Some(letter) => letter.to_string(),
-            None => continue,
-        };
-
-        serialized_words.get_mut(first_letter.as_str()).unwrap().push(word.to_string());
-    }
+/// Whether gamepad typing slows the spawns down, daily runs keep the same pace for everyone
+pub fn slow_spawns(settings: &Settings, run_seed: &RunSeed) -> bool {
+    !run_seed.is_daily() && settings.gamepad_typing
+}
 
-    return serialized_words;
+/// Picks a word (a phrase in phrase runs) and adds it to the `taken_words` so the next words of the same spawn
+/// don't clash with it, `None` when every word clashes
+#[allow(clippy::too_many_arguments)]
+pub fn choose_word(
+    available_words: &AvailableWords,
+    settings: &Settings,
+    run_seed: &RunSeed,
+    game_mode: &GameMode,
+    random: &mut impl Rng,
+    difficulty: f32,
+    taken_words: &mut Vec<String>,
+    keys: Option<&str>,
+) -> Option<String> {
+    // The word lengths and styles don't apply to phrases, they have their own capitals and punctuation
+    let word = if *game_mode == GameMode::Phrases {
+        choose_phrase(available_words, difficulty, taken_words, random)?
+    } else {
+        let word = available_words.choose(difficulty, word_length_limits(settings, run_seed), taken_words, random)?;
+        let styled = word_style(settings, run_seed).apply(word.clone(), taken_words, random);
+        // A versus player only has the `keys` of their half of the keyboard, a twist they can't type is dropped
+        if keys.map_or(true, |keys| is_typed_with(&styled, keys)) { styled } else { word }
+    };
+    taken_words.push(word.clone());
+
+    Some(word)
 }
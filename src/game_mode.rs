@@ -0,0 +1,201 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{GameState, PauseState};
+use crate::events::{RunEnded, WrongKey};
+use crate::menu::ButtonColors;
+use crate::replay::start_replay;
+
+pub struct GameModePlugin;
+
+const TIME_ATTACK_DURATION: Duration = Duration::from_secs(120);
+
+
+/// The rules of the run, picked in the menu. Every single player mode has its own high scores.
+#[derive(Resource, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum GameMode {
+    /// The pile reaching the line ends the run
+    #[default]
+    Endless,
+    /// No line and no difficulty ramp, the pile is swept away when it gets too high
+    Zen,
+    /// The run lasts two minutes
+    TimeAttack,
+    /// Any wrong key ends the run
+    SuddenDeath,
+    /// The trash carries quotes and sentences instead of words, the spaces are typed too
+    Phrases,
+    /// Two players side by side on one keyboard, the first pile to reach its line loses
+    Versus,
+}
+
+#[derive(Resource)]
+struct TimeAttackClock(Timer);
+
+#[derive(Component)]
+struct GameModeToggle;
+
+#[derive(Component)]
+struct TimeAttackClockText;
+
+
+impl Plugin for GameModePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameMode>()
+        .insert_resource(TimeAttackClock(Timer::new(TIME_ATTACK_DURATION, TimerMode::Once)))
+        .add_systems(OnEnter(GameState::Playing), start_time_attack_clock.after(start_replay).run_if(resource_equals(GameMode::TimeAttack)))
+        .add_systems(Update, (
+                tick_time_attack_clock
+                    .run_if(in_state(GameState::Playing)).run_if(in_state(PauseState::Running))
+                    .run_if(resource_equals(GameMode::TimeAttack)),
+                end_run_on_wrong_key
+                    .run_if(in_state(GameState::Playing)).run_if(in_state(PauseState::Running))
+                    .run_if(resource_equals(GameMode::SuddenDeath)),
+                toggle_game_mode.run_if(in_state(GameState::Menu)),
+            ));
+    }
+}
+
+
+impl GameMode {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Endless => "Endless",
+            Self::Zen => "Zen",
+            Self::TimeAttack => "Time Attack",
+            Self::SuddenDeath => "Sudden Death",
+            Self::Phrases => "Phrases",
+            Self::Versus => "Versus",
+        }
+    }
+
+    fn next(&self) -> Self {
+        match self {
+            Self::Endless => Self::Zen,
+            Self::Zen => Self::TimeAttack,
+            Self::TimeAttack => Self::SuddenDeath,
+            Self::SuddenDeath => Self::Phrases,
+            Self::Phrases => Self::Versus,
+            Self::Versus => Self::Endless,
+        }
+    }
+}
+
+
+/// Whether the difficulty goes up during the run
+pub fn has_difficulty_ramp(game_mode: Res<GameMode>) -> bool {
+    *game_mode != GameMode::Zen
+}
+
+/// Whether a single player is typing, versus runs have two (see `Player`)
+pub fn is_solo(game_mode: Res<GameMode>) -> bool {
+    *game_mode != GameMode::Versus
+}
+
+fn start_time_attack_clock(mut commands: Commands, mut clock: ResMut<TimeAttackClock>) {
+    clock.0.reset();
+
+    commands.spawn((
+        TextBundle {
+            text: Text::from_section(
+                format_clock(clock.0.remaining()),
+                TextStyle {
+                    font_size: 40.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(0.0),
+                right: Val::Px(10.0),
+                ..default()
+            },
+            ..default()
+        },
+        TimeAttackClockText,
+    ));
+}
+
+fn tick_time_attack_clock(
+    mut clock: ResMut<TimeAttackClock>,
+    mut run_ended_events: EventWriter<RunEnded>,
+    mut text_query: Query<&mut Text, With<TimeAttackClockText>>,
+    time: Res<Time>,
+) {
+    if clock.0.tick(time.delta()).just_finished() {
+        run_ended_events.send(RunEnded);
+    }
+
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = format_clock(clock.0.remaining());
+    }
+}
+
+fn format_clock(remaining: Duration) -> String {
+    let seconds = remaining.as_secs_f32().ceil() as u32;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+fn end_run_on_wrong_key(
+    mut wrong_key_events: EventReader<WrongKey>,
+    mut run_ended_events: EventWriter<RunEnded>,
+) {
+    // An emptied buffer isn't a key the player missed
+    if wrong_key_events.read().any(|wrong_key| wrong_key.letter.is_some()) {
+        run_ended_events.send(RunEnded);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn toggle_game_mode(
+    mut game_mode: ResMut<GameMode>,
+    interaction_query: Query<(&Interaction, &Children), (Changed<Interaction>, With<GameModeToggle>)>,
+    mut text_query: Query<&mut Text>,
+) {
+    for (interaction, children) in interaction_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        *game_mode = game_mode.next();
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.sections[0].value = format!("Mode: {}", game_mode.label());
+            }
+        }
+    }
+}
+
+pub fn spawn_game_mode_toggle(parent: &mut ChildBuilder, game_mode: &GameMode) {
+    let button_colors = ButtonColors::default();
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(220.0),
+                    height: Val::Px(36.0),
+                    margin: UiRect::top(Val::Px(10.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: button_colors.normal.into(),
+                ..default()
+            },
+            button_colors,
+            GameModeToggle,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                format!("Mode: {}", game_mode.label()),
+                TextStyle {
+                    font_size: 20.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                    ..default()
+                },
+            ));
+        });
+}
@@ -2,8 +2,13 @@ use bevy::prelude::*;
 use bevy_rapier2d::dynamics::Velocity;
 
 use crate::GameState;
+use crate::events::RunEnded;
+use crate::game_mode::GameMode;
+use crate::high_score::{HighScores, LatestRunRank, record_run, spawn_high_score_table};
 use crate::menu::{ButtonColors, ChangeState};
 use crate::score::Score;
+use crate::typing::Player;
+use crate::seed::RunSeed;
 use crate::trash::{Trash, TrashActionActive};
 
 
@@ -19,9 +24,10 @@ pub struct GameOver;
 
 impl Plugin for GameOverPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::GameOver), spawn_game_over_menu)
+        app.add_systems(OnEnter(GameState::GameOver), spawn_game_over_menu.after(record_run))
         .add_systems(OnExit(GameState::GameOver), delete_all_gameover_entities)
         .add_systems(Update, (
+                end_run.run_if(in_state(GameState::Playing)),
                 click_restart_button.run_if(in_state(GameState::GameOver)),
             ));
         // app.insert_resource(TypingBuffer("".to_string()))
@@ -37,6 +43,7 @@ impl Plugin for GameOverPlugin {
 
 
 
+#[allow(clippy::type_complexity)]
 pub fn is_game_over(
     entity: &Entity,
     other: &Entity,
@@ -52,6 +59,17 @@ pub fn is_game_over(
     return false;
 }
 
+fn end_run(
+    mut commands: Commands,
+    mut run_ended_events: EventReader<RunEnded>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if run_ended_events.read().count() > 0 {
+        commands.spawn(GameOver);
+        next_state.set(GameState::GameOver);
+    }
+}
+
 fn delete_all_gameover_entities(
     mut commands: Commands,
     game_over_entities: Query<Entity, With<GameOver>>,
@@ -64,6 +82,10 @@ fn delete_all_gameover_entities(
 fn spawn_game_over_menu(
     mut commands: Commands,
     score: Res<Score>,
+    high_scores: Res<HighScores>,
+    latest_run_rank: Res<LatestRunRank>,
+    run_seed: Res<RunSeed>,
+    game_mode: Res<GameMode>,
 ) {
     let style = Style {
         position_type: PositionType::Absolute,
@@ -88,39 +110,63 @@ fn spawn_game_over_menu(
     .insert(Transform::from_translation(Vec3::new(0.0, 0.0, 0.0)))
     .insert(GameOver);
 
-    commands.spawn(
-        TextBundle {
-            text: Text {
-                sections: vec![
-                    TextSection {
-                        value: "Score: ".to_string(),
-                        style: TextStyle {
-                            font_size: 50.0,
-                            color: Color::WHITE,
-                            ..default()
+    // `VersusPlugin` shows who won instead
+    if *game_mode != GameMode::Versus {
+        commands.spawn(
+            TextBundle {
+                text: Text {
+                    sections: vec![
+                        TextSection {
+                            value: "Score: ".to_string(),
+                            style: TextStyle {
+                                font_size: 50.0,
+                                color: Color::WHITE,
+                                ..default()
+                            },
                         },
-                    },
-                    TextSection {
-                        value: score.0.to_string(),
-                        style: TextStyle {
-                            font_size: 50.0,
-                            color: Color::GREEN,
-                            ..default()
+                        TextSection {
+                            value: score.0[Player::One].to_string(),
+                            style: TextStyle {
+                                font_size: 50.0,
+                                color: Color::GREEN,
+                                ..default()
+                            },
                         },
-                    },
-                ],
+                    ],
+                    ..default()
+                },
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(100.0),
+                    left: Val::Px(120.0),
+                    ..default()
+                },
                 ..default()
             },
+        )
+        .insert(Transform::from_translation(Vec3::new(0.0, 0.0, 0.0)))
+        .insert(GameOver);
+    }
+
+    commands.spawn(
+        TextBundle {
+            text: Text::from_section(
+                format!("{}  {}", game_mode.label(), run_seed.label()),
+                TextStyle {
+                    font_size: 25.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                    ..default()
+                }
+            ),
             style: Style {
                 position_type: PositionType::Absolute,
-                top: Val::Px(100.0),
+                top: Val::Px(160.0),
                 left: Val::Px(120.0),
                 ..default()
             },
             ..default()
         },
     )
-    .insert(Transform::from_translation(Vec3::new(0.0, 0.0, 0.0)))
     .insert(GameOver);
 
 
@@ -151,8 +197,13 @@ fn spawn_game_over_menu(
             },
         ));
     });
+
+    if *game_mode != GameMode::Versus {
+        spawn_high_score_table(&mut commands, &high_scores, &latest_run_rank, Val::Percent(60.0));
+    }
 }
 
+#[allow(clippy::type_complexity)]
 fn click_restart_button(
     mut next_state: ResMut<NextState<GameState>>,
     mut interaction_query: Query<
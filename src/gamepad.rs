@@ -0,0 +1,303 @@
+use std::collections::BTreeSet;
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::{GameState, PauseState};
+use crate::game::word_style;
+use crate::replay::{is_live, start_replay};
+use crate::seed::RunSeed;
+use crate::settings::Settings;
+use crate::sorting::with_bin_keys;
+use crate::typing::{KeyEdit, TypingKey, read_typing_keys, shifted};
+use crate::words::{AvailableWords, build_available_words};
+
+pub struct GamepadTypingPlugin;
+
+const SECTORS: usize = 8;
+const STICK_DEAD_ZONE: f32 = 0.5;
+/// The face buttons in the order of the characters of a sector, drawn as a cross
+const FACE_BUTTONS: [GamepadButtonType; 4] = [
+    GamepadButtonType::North,
+    GamepadButtonType::West,
+    GamepadButtonType::East,
+    GamepadButtonType::South,
+];
+const SECTOR_SIZE: f32 = 56.0;
+const RING_RADIUS: f32 = 64.0;
+const SECTOR_COLOR: Color = Color::rgba(0.15, 0.15, 0.15, 0.7);
+const SELECTED_SECTOR_COLOR: Color = Color::rgba(0.15, 0.35, 0.6, 0.9);
+
+
+/// A ring of sectors around each stick, pointing a stick at a sector
+/// lets the face buttons type its characters
+#[derive(Resource, Default)]
+struct LetterPicker {
+    /// The characters of each sector, a to z around the left stick and the rest of the run's characters around the right one
+    rings: [Vec<Vec<char>>; 2],
+    /// The ring and the sector a stick points at
+    selected: Option<(usize, usize)>,
+}
+
+#[derive(Component)]
+struct LetterPickerRing;
+
+#[derive(Component)]
+struct PickerSector {
+    ring: usize,
+    sector: usize,
+}
+
+
+/// Typing with a gamepad: the sticks pick a sector, the face buttons a character of it,
+/// the left trigger types capitals, the bumpers are backspace and clear and start pauses
+impl Plugin for GamepadTypingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LetterPicker>()
+        .add_systems(OnEnter(GameState::Playing), (
+                // Replays with gamepad typing need the same words, so this isn't only for live runs
+                fit_words_to_picker.after(start_replay).after(build_available_words),
+                spawn_letter_picker.after(fit_words_to_picker).run_if(is_live),
+            ))
+        .add_systems(OnExit(GameState::Playing), cleanup_letter_picker)
+        .add_systems(Update, (
+                // Before the keyboard is read so the keys are recorded on the frame they're typed
+                pick_letters.before(read_typing_keys)
+                    .run_if(in_state(GameState::Playing)).run_if(in_state(PauseState::Running)).run_if(is_live)
+                    .run_if(|settings: Res<Settings>| settings.gamepad_typing),
+                highlight_selected_sector.after(pick_letters).run_if(in_state(GameState::Playing)),
+                toggle_pause_with_gamepad.run_if(in_state(GameState::Playing)),
+            ));
+    }
+}
+
+
+/// The sector a stick points at, starting from the top and going clockwise
+fn stick_sector(stick: Vec2) -> Option<usize> {
+    if stick.length() < STICK_DEAD_ZONE {
+        return None;
+    }
+
+    let angle = stick.x.atan2(stick.y);
+    Some(((angle / TAU * SECTORS as f32).round() as i32).rem_euclid(SECTORS as i32) as usize)
+}
+
+/// Lays out the rings, the right one only has room for so many characters so the ones the fewest words use
+/// are left out along with their words
+fn fit_words_to_picker(
+    mut picker: ResMut<LetterPicker>,
+    mut available_words: ResMut<AvailableWords>,
+    settings: Res<Settings>,
+    run_seed: Res<RunSeed>,
+) {
+    picker.selected = None;
+    if !settings.gamepad_typing {
+        return;
+    }
+
+    // The characters the word style adds and the bin keys don't come from the words, they always fit
+    let word_style = word_style(&settings, &run_seed);
+    let needed: BTreeSet<char> = with_bin_keys(word_style.characters(), &settings, &run_seed).into_iter().collect();
+    let mut word_counts: HashMap<char, usize> = HashMap::new();
+    for available_word in available_words.0.iter() {
+        let characters: BTreeSet<char> = available_word.word.chars().flat_map(char::to_lowercase).collect();
+        for character in characters.into_iter().filter(|character| !character.is_ascii_lowercase() && !needed.contains(character)) {
+            *word_counts.entry(character).or_default() += 1;
+        }
+    }
+    let mut by_use: Vec<(char, usize)> = word_counts.into_iter().collect();
+    by_use.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    let room = (SECTORS * FACE_BUTTONS.len()).saturating_sub(needed.len());
+    let left_out: Vec<char> = by_use.iter().skip(room).map(|(character, _)| *character).collect();
+    let characters: BTreeSet<char> = needed.into_iter().chain(by_use.into_iter().take(room).map(|(character, _)| character)).collect();
+
+    if !left_out.is_empty() {
+        let typeable: String = ('a'..='z').chain(characters.iter().copied()).collect();
+        let words = available_words.typed_with(&typeable);
+        if words.0.is_empty() {
+            // An empty list would leave nothing to spawn, the words with these characters just can't be finished
+            warn!("The gamepad picker has no room for {:?}, which every word uses, keeping all the words", left_out);
+        } else {
+            warn!(
+                "The gamepad picker has no room for {:?}, leaving out the {} words using them",
+                left_out,
+                available_words.0.len() - words.0.len(),
+            );
+            *available_words = words;
+        }
+    }
+
+    let sectors = |characters: Vec<char>| -> Vec<Vec<char>> {
+        characters.chunks(FACE_BUTTONS.len()).take(SECTORS).map(<[char]>::to_vec).collect()
+    };
+    picker.rings = [
+        sectors(('a'..='z').collect()),
+        sectors(characters.into_iter().collect()),
+    ];
+}
+
+fn spawn_letter_picker(
+    mut commands: Commands,
+    picker: Res<LetterPicker>,
+    settings: Res<Settings>,
+) {
+    if !settings.gamepad_typing {
+        return;
+    }
+
+    for (ring, sectors) in picker.rings.iter().enumerate() {
+        if sectors.is_empty() {
+            continue;
+        }
+
+        let ring_size = 2.0 * RING_RADIUS + SECTOR_SIZE;
+        let (left, right) = if ring == 0 { (Val::Px(10.0), Val::Auto) } else { (Val::Auto, Val::Px(10.0)) };
+        commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        bottom: Val::Px(10.0),
+                        left,
+                        right,
+                        width: Val::Px(ring_size),
+                        height: Val::Px(ring_size),
+                        ..default()
+                    },
+                    z_index: ZIndex::Global(5),
+                    ..default()
+                },
+                LetterPickerRing,
+            ))
+            .with_children(|parent| {
+                for (sector, characters) in sectors.iter().enumerate() {
+                    let angle = sector as f32 / SECTORS as f32 * TAU;
+                    let label = match characters.as_slice() {
+                        [north, west, east, south] => format!("{north}\n{west} {east}\n{south}"),
+                        [north, west, east] => format!("{north}\n{west} {east}"),
+                        [north, west] => format!("{north}\n{west}"),
+                        _ => characters.iter().collect(),
+                    };
+
+                    parent
+                        .spawn((
+                            NodeBundle {
+                                style: Style {
+                                    position_type: PositionType::Absolute,
+                                    left: Val::Px(RING_RADIUS + RING_RADIUS * angle.sin()),
+                                    top: Val::Px(RING_RADIUS - RING_RADIUS * angle.cos()),
+                                    width: Val::Px(SECTOR_SIZE),
+                                    height: Val::Px(SECTOR_SIZE),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                background_color: SECTOR_COLOR.into(),
+                                ..default()
+                            },
+                            PickerSector { ring, sector },
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                label,
+                                TextStyle {
+                                    font_size: 16.0,
+                                    color: Color::rgb(0.9, 0.9, 0.9),
+                                    ..default()
+                                },
+                            )
+                            .with_text_alignment(TextAlignment::Center));
+                        });
+                }
+            });
+    }
+}
+
+fn pick_letters(
+    gamepads: Res<Gamepads>,
+    axes: Res<Axis<GamepadAxis>>,
+    buttons: Res<Input<GamepadButton>>,
+    mut picker: ResMut<LetterPicker>,
+    mut typing_keys: EventWriter<TypingKey>,
+) {
+    let mut selected = None;
+
+    for gamepad in gamepads.iter() {
+        let stick = |x: GamepadAxisType, y: GamepadAxisType| Vec2::new(
+            axes.get(GamepadAxis::new(gamepad, x)).unwrap_or(0.0),
+            axes.get(GamepadAxis::new(gamepad, y)).unwrap_or(0.0),
+        );
+        let pressed = |button_type: GamepadButtonType| buttons.just_pressed(GamepadButton::new(gamepad, button_type));
+
+        if pressed(GamepadButtonType::LeftTrigger) {
+            typing_keys.send(KeyEdit::Backspace.into());
+        }
+        if pressed(GamepadButtonType::RightTrigger) {
+            typing_keys.send(KeyEdit::Clear.into());
+        }
+
+        // The left stick wins when both are pushed
+        let Some((ring, sector)) = stick_sector(stick(GamepadAxisType::LeftStickX, GamepadAxisType::LeftStickY))
+            .map(|sector| (0, sector))
+            .or_else(|| stick_sector(stick(GamepadAxisType::RightStickX, GamepadAxisType::RightStickY)).map(|sector| (1, sector)))
+        else {
+            continue;
+        };
+        selected = Some((ring, sector));
+
+        let capital = buttons.pressed(GamepadButton::new(gamepad, GamepadButtonType::LeftTrigger2));
+        for (index, button_type) in FACE_BUTTONS.into_iter().enumerate() {
+            let character = picker.rings[ring].get(sector).and_then(|characters| characters.get(index));
+            if let (true, Some(character)) = (pressed(button_type), character) {
+                typing_keys.send(KeyEdit::Letter(shifted(*character, capital)).into());
+            }
+        }
+    }
+
+    if picker.selected != selected {
+        picker.selected = selected;
+    }
+}
+
+fn highlight_selected_sector(
+    picker: Res<LetterPicker>,
+    mut sector_query: Query<(&PickerSector, &mut BackgroundColor)>,
+) {
+    if !picker.is_changed() {
+        return;
+    }
+
+    for (picker_sector, mut background_color) in sector_query.iter_mut() {
+        *background_color = if picker.selected == Some((picker_sector.ring, picker_sector.sector)) {
+            SELECTED_SECTOR_COLOR
+        } else {
+            SECTOR_COLOR
+        }.into();
+    }
+}
+
+fn toggle_pause_with_gamepad(
+    gamepads: Res<Gamepads>,
+    buttons: Res<Input<GamepadButton>>,
+    pause_state: Res<State<PauseState>>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+) {
+    let start_pressed = gamepads.iter()
+        .any(|gamepad| buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::Start)));
+    if !start_pressed {
+        return;
+    }
+
+    match pause_state.get() {
+        PauseState::Running => next_pause_state.set(PauseState::Paused),
+        PauseState::Paused => next_pause_state.set(PauseState::Running),
+    }
+}
+
+fn cleanup_letter_picker(mut commands: Commands, rings: Query<Entity, With<LetterPickerRing>>) {
+    for entity in rings.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,147 @@
+use bevy::gizmos::GizmoPlugin;
+use bevy::input::InputPlugin;
+use bevy::prelude::*;
+use bevy::render::render_resource::Shader;
+use bevy::window::WindowResolution;
+use bevy_progressbar::ProgressBarMaterial;
+use bevy_rapier2d::plugin::{NoUserData, RapierPhysicsPlugin};
+
+use crate::{GameRulesPlugin, GameState};
+use crate::catalogue::TrashCatalogue;
+use crate::difficulty::DifficultyProfiles;
+use crate::game::{WINDOW_HEIGHT, WINDOW_WIDTH};
+use crate::loading::{DataAssets, TextureAssets};
+use crate::phrases::{PhraseList, parse_phrases};
+use crate::seed::SeedSettings;
+use crate::trash::Trash;
+use crate::trash_text::TrashText;
+use crate::typing::{KeyEdit, TypingKey};
+use crate::words::{WordList, parse_words};
+
+// The game without a window, a renderer or audio, for the tests.
+// The assets are read from the repository instead of going through the loading state,
+// the textures are left as default handles since nothing is drawn.
+
+const WORD_PACKS: [(&str, &str); 4] = [
+    ("everyday", include_str!("../assets/words/everyday.words")),
+    ("recycling", include_str!("../assets/words/recycling.words")),
+    ("programming", include_str!("../assets/words/programming.words")),
+    ("deutsch", include_str!("../assets/words/deutsch.words")),
+];
+const PHRASE_LISTS: [(&str, &str); 1] = [
+    ("quotes", include_str!("../assets/phrases/quotes.phrases")),
+];
+
+
+/// Builds the app of a run with the given seed, the run starts on the first update
+pub fn headless_app(seed: u64) -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin::default(),
+        TransformPlugin,
+        HierarchyPlugin,
+        InputPlugin,
+    ))
+    // The gizmos and the combo meter only need their assets to exist
+    .init_asset::<Shader>()
+    .init_asset::<ProgressBarMaterial>()
+    .init_asset::<TrashCatalogue>()
+    .init_asset::<DifficultyProfiles>()
+    // The typed characters normally come from the window
+    .add_event::<ReceivedCharacter>()
+    .add_plugins((
+        GizmoPlugin,
+        RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(100.0),
+        GameRulesPlugin,
+    ));
+
+    app.insert_resource(TextureAssets {
+        bevy: Handle::default(),
+        github: Handle::default(),
+        ground: Handle::default(),
+        wall: Handle::default(),
+        background: Handle::default(),
+    });
+
+    let catalogue: TrashCatalogue = ron::from_str(include_str!("../assets/trash.catalogue.ron"))
+        .expect("the trash catalogue is valid");
+    let trash_catalogue = app.world.resource_mut::<Assets<TrashCatalogue>>().add(catalogue);
+    let profiles: DifficultyProfiles = ron::from_str(include_str!("../assets/difficulty.profiles.ron"))
+        .expect("the difficulty profiles are valid");
+    let difficulty_profiles = app.world.resource_mut::<Assets<DifficultyProfiles>>().add(profiles);
+    let word_lists = WORD_PACKS.iter()
+        .map(|(name, text)| {
+            app.world.resource_mut::<Assets<WordList>>().add(WordList {
+                name: name.to_string(),
+                words: parse_words(text).0,
+            })
+        })
+        .collect();
+    let phrase_lists = PHRASE_LISTS.iter()
+        .map(|(name, text)| {
+            app.world.resource_mut::<Assets<PhraseList>>().add(PhraseList {
+                name: name.to_string(),
+                phrases: parse_phrases(text).0,
+            })
+        })
+        .collect();
+    app.insert_resource(DataAssets { trash_catalogue, difficulty_profiles, word_lists, phrase_lists });
+
+    app.insert_resource(SeedSettings {
+        daily: false,
+        custom_seed: Some(seed),
+    });
+    app.world.spawn(Window {
+        resolution: WindowResolution::new(WINDOW_WIDTH, WINDOW_HEIGHT),
+        ..default()
+    });
+    app.world.resource_mut::<NextState<GameState>>().set(GameState::Playing);
+
+    app
+}
+
+pub fn run_frames(app: &mut App, frames: u32) {
+    for _ in 0..frames {
+        app.update();
+    }
+}
+
+/// Runs frames until the condition holds, returns false if it didn't within `max_frames`
+pub fn run_until(app: &mut App, max_frames: u32, mut condition: impl FnMut(&mut World) -> bool) -> bool {
+    for _ in 0..max_frames {
+        app.update();
+        if condition(&mut app.world) {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Types the letters one frame apart, the way a (very fast) player would
+pub fn type_letters(app: &mut App, letters: &str) {
+    for letter in letters.chars() {
+        app.world.send_event(TypingKey::from(KeyEdit::Letter(letter)));
+        app.update();
+    }
+}
+
+/// The trash that still has a word, with its word
+pub fn words_on_screen(world: &mut World) -> Vec<(Entity, Trash, String)> {
+    let mut words: Vec<(Entity, Trash, String)> = world
+        .query::<(&Parent, &TrashText)>()
+        .iter(world)
+        .map(|(parent, trash_text)| (parent.get(), trash_text.word.clone()))
+        .collect::<Vec<(Entity, String)>>()
+        .into_iter()
+        .filter_map(|(entity, word)| Some((entity, world.get::<Trash>(entity)?.clone(), word)))
+        .collect();
+    words.sort_by_key(|(entity, _, _)| *entity);
+
+    words
+}
+
+pub fn current_state(app: &App) -> GameState {
+    app.world.resource::<State<GameState>>().get().clone()
+}
@@ -0,0 +1,220 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{GameState, PauseState};
+use crate::game_mode::{GameMode, is_solo};
+use crate::game_over::GameOver;
+use crate::replay::is_live;
+use crate::save;
+use crate::events::{ComboLevelUp, WordCompleted};
+use crate::score::Score;
+use crate::typing::Player;
+use crate::typing_stats::{TypingStats, TypingSummary};
+
+pub struct HighScorePlugin;
+
+pub const MAX_HIGH_SCORES: usize = 10;
+const HIGH_SCORES_SAVE_NAME: &str = "high_scores";
+
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HighScoreEntry {
+    pub score: usize,
+    pub peak_combo: usize,
+    pub words_cleared: usize,
+    pub duration_seconds: f32,
+    pub date: String,
+    #[serde(default)]
+    pub typing: TypingSummary,
+}
+
+/// The top runs of the selected game mode sorted from the highest score to the lowest
+#[derive(Resource, Serialize, Deserialize, Default)]
+pub struct HighScores(pub Vec<HighScoreEntry>);
+
+/// Everything about the current run that isn't already tracked by `Score`
+#[derive(Resource, Default)]
+pub struct RunStats {
+    pub peak_combo: usize,
+    pub words_cleared: usize,
+    pub duration_seconds: f32,
+}
+
+/// The position of the run that just ended in `HighScores`, `None` if it didn't make the table
+#[derive(Resource, Default)]
+pub struct LatestRunRank(pub Option<usize>);
+
+
+impl Plugin for HighScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HighScores>()
+        .init_resource::<RunStats>()
+        .init_resource::<LatestRunRank>()
+        .add_systems(OnEnter(GameState::Playing), reset_run_stats)
+        // A versus run has a score per side, none of them goes in the table
+        .add_systems(OnEnter(GameState::GameOver), record_run.run_if(is_live).run_if(is_solo))
+        .add_systems(Update, (
+                load_high_scores.run_if(resource_changed::<GameMode>()),
+                update_run_stats.run_if(in_state(GameState::Playing)).run_if(in_state(PauseState::Running)),
+            ));
+    }
+}
+
+
+impl HighScores {
+    /// Inserts the entry at its rank and drops everything past `MAX_HIGH_SCORES`.
+    /// Returns the rank of the new entry if it made the table.
+    pub fn insert(&mut self, entry: HighScoreEntry) -> Option<usize> {
+        let rank = self.0.iter().position(|existing| entry.score > existing.score).unwrap_or(self.0.len());
+        if rank >= MAX_HIGH_SCORES {
+            return None;
+        }
+
+        self.0.insert(rank, entry);
+        self.0.truncate(MAX_HIGH_SCORES);
+        Some(rank)
+    }
+}
+
+
+/// Endless keeps the save of the high scores from before there were game modes
+fn save_name(game_mode: GameMode) -> String {
+    match game_mode {
+        GameMode::Endless => HIGH_SCORES_SAVE_NAME.to_string(),
+        _ => format!("{HIGH_SCORES_SAVE_NAME}_{game_mode:?}").to_lowercase(),
+    }
+}
+
+fn load_high_scores(game_mode: Res<GameMode>, mut high_scores: ResMut<HighScores>) {
+    *high_scores = save::load::<HighScores>(&save_name(*game_mode)).unwrap_or_default();
+}
+
+/// Replays and versus runs aren't recorded, the rank of the previous run mustn't show on their game over screen
+fn reset_run_stats(mut run_stats: ResMut<RunStats>, mut latest_run_rank: ResMut<LatestRunRank>) {
+    *run_stats = RunStats {
+        peak_combo: 1,
+        ..default()
+    };
+    latest_run_rank.0 = None;
+}
+
+fn update_run_stats(
+    mut run_stats: ResMut<RunStats>,
+    mut word_completed_events: EventReader<WordCompleted>,
+    mut combo_level_up_events: EventReader<ComboLevelUp>,
+    time: Res<Time>,
+) {
+    run_stats.duration_seconds += time.delta_seconds();
+    run_stats.words_cleared += word_completed_events.read().count();
+    for combo_level_up in combo_level_up_events.read() {
+        run_stats.peak_combo = run_stats.peak_combo.max(combo_level_up.level);
+    }
+}
+
+pub fn record_run(
+    mut high_scores: ResMut<HighScores>,
+    mut latest_run_rank: ResMut<LatestRunRank>,
+    score: Res<Score>,
+    run_stats: Res<RunStats>,
+    typing_stats: Res<TypingStats>,
+    game_mode: Res<GameMode>,
+) {
+    latest_run_rank.0 = high_scores.insert(HighScoreEntry {
+        score: score.0[Player::One],
+        peak_combo: run_stats.peak_combo,
+        words_cleared: run_stats.words_cleared,
+        duration_seconds: run_stats.duration_seconds,
+        date: chrono::Local::now().format("%Y-%m-%d").to_string(),
+        typing: typing_stats.summary(run_stats.duration_seconds),
+    });
+
+    save::save(&save_name(*game_mode), &*high_scores);
+}
+
+
+fn format_duration(seconds: f32) -> String {
+    let seconds = seconds as u32;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+fn spawn_row(parent: &mut ChildBuilder, values: [String; 7], color: Color) {
+    const COLUMN_WIDTHS: [f32; 7] = [40.0, 90.0, 80.0, 80.0, 60.0, 80.0, 130.0];
+
+    parent.spawn(NodeBundle {
+        style: Style {
+            flex_direction: FlexDirection::Row,
+            ..default()
+        },
+        ..default()
+    })
+    .with_children(|row| {
+        for (value, width) in values.into_iter().zip(COLUMN_WIDTHS) {
+            row.spawn(TextBundle {
+                text: Text::from_section(
+                    value,
+                    TextStyle {
+                        font_size: 20.0,
+                        color,
+                        ..default()
+                    }
+                ),
+                style: Style {
+                    width: Val::Px(width),
+                    ..default()
+                },
+                ..default()
+            });
+        }
+    });
+}
+
+/// Spawns the ranked table on the game over screen, the run that just ended is highlighted
+pub fn spawn_high_score_table(
+    commands: &mut Commands,
+    high_scores: &HighScores,
+    latest_run_rank: &LatestRunRank,
+    top: Val,
+) {
+    commands.spawn(
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Column,
+                top,
+                left: Val::Px(70.0),
+                ..default()
+            },
+            ..default()
+        }
+    )
+    .insert(GameOver)
+    .with_children(|parent| {
+        spawn_row(
+            parent,
+            ["#", "Score", "Combo", "Words", "WPM", "Time", "Date"].map(String::from),
+            Color::GRAY,
+        );
+
+        for (rank, entry) in high_scores.0.iter().enumerate() {
+            let color = if latest_run_rank.0 == Some(rank) {
+                Color::YELLOW
+            } else {
+                Color::WHITE
+            };
+
+            spawn_row(
+                parent,
+                [
+                    (rank + 1).to_string(),
+                    entry.score.to_string(),
+                    format!("x{}", entry.peak_combo),
+                    entry.words_cleared.to_string(),
+                    format!("{:.0}", entry.typing.words_per_minute),
+                    format_duration(entry.duration_seconds),
+                    entry.date.clone(),
+                ],
+                color,
+            );
+        }
+    });
+}
@@ -1,21 +1,45 @@
-#![allow(clippy::type_complexity)]
-
 mod actions;
 mod audio;
+mod catalogue;
+mod difficulty;
+mod events;
+mod explosion;
 mod loading;
 mod menu;
+mod pause;
+mod phrases;
+mod power_ups;
+mod replay;
 mod player;
 mod trash;
 mod typing;
+mod words;
 mod trash_text;
 mod score;
 mod clone_entity;
 mod game;
 mod game_over;
+mod game_mode;
+mod gamepad;
+mod high_score;
+mod save;
+mod seed;
+mod settings;
+mod sorting;
+mod target_lock;
+mod on_screen_keyboard;
+mod typing_stats;
+mod versus;
+#[cfg(test)]
+mod headless;
+#[cfg(test)]
+mod tests;
 // mod consts;
 
 use crate::actions::ActionsPlugin;
 use crate::audio::InternalAudioPlugin;
+use crate::events::GameplayEventsPlugin;
+use crate::explosion::ExplosionPlugin;
 use crate::loading::LoadingPlugin;
 use crate::menu::MenuPlugin;
 // use crate::player::PlayerPlugin;
@@ -26,10 +50,24 @@ use bevy::prelude::*;
 use bevy_progressbar::ProgressBarPlugin;
 
 use self::game::PlayPlugin;
+use self::game_mode::GameModePlugin;
 use self::game_over::GameOverPlugin;
+use self::gamepad::GamepadTypingPlugin;
+use self::high_score::HighScorePlugin;
+use self::on_screen_keyboard::OnScreenKeyboardPlugin;
+use self::pause::PausePlugin;
+use self::power_ups::PowerUpsPlugin;
+use self::replay::ReplayPlugin;
 use self::score::ScorePlugin;
+use self::seed::SeedPlugin;
+use self::settings::SettingsPlugin;
+use self::sorting::SortingPlugin;
+use self::target_lock::TargetLockPlugin;
 use self::trash::TrashPlugin;
 use self::typing::TypingPlugin;
+use self::typing_stats::TypingStatsPlugin;
+use self::versus::VersusPlugin;
+use self::words::WordsPlugin;
 
 // This example game uses States to separate logic
 // See https://bevy-cheatbook.github.io/programming/states.html
@@ -44,29 +82,55 @@ enum GameState {
     // Here the menu is drawn and waiting for player interaction
     Menu,
     GameOver,
+    // The settings screen, opened from the menu
+    Settings,
+    // Passed through for a single frame to restart a run from the pause menu
+    Restarting,
+}
+
+// Runs alongside `GameState::Playing` so pausing doesn't exit the playing state (which deletes the playfield)
+#[derive(States, Default, Clone, Eq, PartialEq, Debug, Hash)]
+enum PauseState {
+    #[default]
+    Running,
+    Paused,
 }
 
 pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
-        app.add_state::<GameState>().add_plugins((
-            LoadingPlugin,
+        app.add_plugins((LoadingPlugin, InternalAudioPlugin, ProgressBarPlugin, GameRulesPlugin));
+
+        #[cfg(debug_assertions)]
+        {
+            // app.add_plugins((FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin::default()));
+        }
+    }
+}
+
+// The states and every gameplay plugin, without the asset loading, the audio and the rendering only plugins.
+// The headless tests run this on top of `MinimalPlugins`.
+struct GameRulesPlugin;
+
+impl Plugin for GameRulesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state::<GameState>().add_state::<PauseState>().add_plugins((GameplayEventsPlugin, SeedPlugin, ReplayPlugin, TypingStatsPlugin, GameModePlugin, SortingPlugin, PowerUpsPlugin, ExplosionPlugin, VersusPlugin)).add_plugins((
             MenuPlugin,
             ActionsPlugin,
-            InternalAudioPlugin,
-            ProgressBarPlugin,
             TypingPlugin,
             PlayPlugin,
             GameOverPlugin,
             ScorePlugin,
             TrashPlugin,
+            HighScorePlugin,
+            WordsPlugin,
+            TargetLockPlugin,
+            PausePlugin,
+            SettingsPlugin,
+            OnScreenKeyboardPlugin,
+            GamepadTypingPlugin,
             // PlayerPlugin,
         ));
-
-        #[cfg(debug_assertions)]
-        {
-            // app.add_plugins((FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin::default()));
-        }
     }
 }
@@ -1,4 +1,8 @@
 use crate::GameState;
+use crate::catalogue::{TrashCatalogue, TrashCatalogueLoader};
+use crate::difficulty::{DifficultyProfiles, DifficultyProfilesLoader};
+use crate::phrases::PhraseList;
+use crate::words::WordList;
 use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
 use bevy_kira_audio::AudioSource;
@@ -10,11 +14,16 @@ pub struct LoadingPlugin;
 /// If interested, take a look at <https://bevy-cheatbook.github.io/features/assets.html>
 impl Plugin for LoadingPlugin {
     fn build(&self, app: &mut App) {
-        app.add_loading_state(
+        app.init_asset::<TrashCatalogue>()
+        .init_asset_loader::<TrashCatalogueLoader>()
+        .init_asset::<DifficultyProfiles>()
+        .init_asset_loader::<DifficultyProfilesLoader>()
+        .add_loading_state(
             LoadingState::new(GameState::Loading).continue_to_state(GameState::Menu),
         )
         .add_collection_to_loading_state::<_, AudioAssets>(GameState::Loading)
-        .add_collection_to_loading_state::<_, TextureAssets>(GameState::Loading);
+        .add_collection_to_loading_state::<_, TextureAssets>(GameState::Loading)
+        .add_collection_to_loading_state::<_, DataAssets>(GameState::Loading);
     }
 }
 
@@ -29,6 +38,18 @@ pub struct AudioAssets {
     pub play: Handle<AudioSource>,
     #[asset(path = "audio/game_over.ogg")]
     pub game_over: Handle<AudioSource>,
+    #[asset(path = "audio/keystroke.wav")]
+    pub keystroke: Handle<AudioSource>,
+    #[asset(path = "audio/mistake.wav")]
+    pub mistake: Handle<AudioSource>,
+    #[asset(path = "audio/word_cleared.wav")]
+    pub word_cleared: Handle<AudioSource>,
+    #[asset(path = "audio/combo_up.wav")]
+    pub combo_up: Handle<AudioSource>,
+    #[asset(path = "audio/explosion.wav")]
+    pub explosion: Handle<AudioSource>,
+    #[asset(path = "audio/landing.wav")]
+    pub landing: Handle<AudioSource>,
 }
 
 #[derive(AssetCollection, Resource)]
@@ -37,24 +58,6 @@ pub struct TextureAssets {
     pub bevy: Handle<Image>,
     #[asset(path = "textures/github.png")]
     pub github: Handle<Image>,
-    #[asset(path = "textures/bottle.png")]
-    pub bottle: Handle<Image>,
-    #[asset(path = "textures/pizza.png")]
-    pub pizza: Handle<Image>,
-    #[asset(path = "textures/big_box.png")]
-    pub big_box: Handle<Image>,
-    #[asset(path = "textures/glass_bottle.png")]
-    pub glass_bottle: Handle<Image>,
-    #[asset(path = "textures/news.png")]
-    pub news: Handle<Image>,
-    #[asset(path = "textures/shampo.png")]
-    pub shampoo: Handle<Image>,
-    #[asset(path = "textures/small_can.png")]
-    pub small_can: Handle<Image>,
-    #[asset(path = "textures/soda.png")]
-    pub soda: Handle<Image>,
-    #[asset(path = "textures/spray.png")]
-    pub spray: Handle<Image>,
     #[asset(path = "textures/ground.png")]
     pub ground: Handle<Image>,
     #[asset(path = "textures/wall.png")]
@@ -62,3 +65,23 @@ pub struct TextureAssets {
     #[asset(path = "textures/background-1.png")]
     pub background: Handle<Image>,
 }
+
+#[derive(AssetCollection, Resource)]
+pub struct DataAssets {
+    #[asset(path = "trash.catalogue.ron")]
+    pub trash_catalogue: Handle<TrashCatalogue>,
+    #[asset(path = "difficulty.profiles.ron")]
+    pub difficulty_profiles: Handle<DifficultyProfiles>,
+    #[asset(
+        paths(
+            "words/everyday.words",
+            "words/recycling.words",
+            "words/programming.words",
+            "words/deutsch.words"
+        ),
+        collection(typed)
+    )]
+    pub word_lists: Vec<Handle<WordList>>,
+    #[asset(paths("phrases/quotes.phrases"), collection(typed))]
+    pub phrase_lists: Vec<Handle<PhraseList>>,
+}
@@ -1,5 +1,8 @@
+use crate::game_mode::{GameMode, spawn_game_mode_toggle};
 use crate::loading::TextureAssets;
+use crate::target_lock::{TargetLock, spawn_target_lock_toggle};
 use crate::GameState;
+use bevy::ecs::system::EntityCommands;
 use bevy::prelude::*;
 
 pub struct MenuPlugin;
@@ -9,7 +12,11 @@ pub struct MenuPlugin;
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(OnEnter(GameState::Menu), setup_menu)
-            .add_systems(Update, click_play_button.run_if(in_state(GameState::Menu)))
+            .add_systems(Update, (
+                click_play_button.run_if(in_state(GameState::Menu)),
+                focus_text_inputs.run_if(in_state(GameState::Menu)),
+                type_into_text_inputs.after(focus_text_inputs).run_if(in_state(GameState::Menu)),
+            ))
             .add_systems(OnExit(GameState::Menu), cleanup_menu);
     }
 }
@@ -32,13 +39,33 @@ impl Default for ButtonColors {
 #[derive(Component)]
 struct Menu;
 
-fn setup_menu(mut commands: Commands, textures: Res<TextureAssets>, window: Query<&Window>) {
+/// A single line text field, clicking it focuses it and typed characters are appended to `value`
+#[derive(Component)]
+pub struct TextInput {
+    pub value: String,
+    pub placeholder: &'static str,
+    pub max_length: usize,
+    pub accepts: fn(char) -> bool,
+    pub focused: bool,
+}
+
+fn setup_menu(
+    mut commands: Commands,
+    textures: Res<TextureAssets>,
+    target_lock: Res<TargetLock>,
+    game_mode: Res<GameMode>,
+    window: Query<&Window>,
+    camera: Query<(), With<Camera>>,
+) {
     let window_height = window.single().height() / 2.0;
 
-    commands.spawn(Camera2dBundle {
-        transform: Transform::from_translation(Vec3::new(0.0, window_height, 0.0)),
-        ..default()
-    });
+    // The camera outlives the menu, it's only spawned the first time the menu is shown
+    if camera.is_empty() {
+        commands.spawn(Camera2dBundle {
+            transform: Transform::from_translation(Vec3::new(0.0, window_height, 0.0)),
+            ..default()
+        });
+    }
     commands
         .spawn((
             NodeBundle {
@@ -82,6 +109,35 @@ fn setup_menu(mut commands: Commands, textures: Res<TextureAssets>, window: Quer
                         },
                     ));
                 });
+            children
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(220.0),
+                            height: Val::Px(36.0),
+                            margin: UiRect::top(Val::Px(10.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        background_color: ButtonColors::default().normal.into(),
+                        ..Default::default()
+                    },
+                    ButtonColors::default(),
+                    ChangeState(GameState::Settings),
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Settings",
+                        TextStyle {
+                            font_size: 20.0,
+                            color: Color::rgb(0.9, 0.9, 0.9),
+                            ..default()
+                        },
+                    ));
+                });
+            spawn_game_mode_toggle(children, &game_mode);
+            spawn_target_lock_toggle(children, &target_lock);
         });
     commands
         .spawn((
@@ -185,6 +241,7 @@ pub struct ChangeState(pub GameState);
 #[derive(Component)]
 struct OpenLink(&'static str);
 
+#[allow(clippy::type_complexity)]
 fn click_play_button(
     mut next_state: ResMut<NextState<GameState>>,
     mut interaction_query: Query<
@@ -219,6 +276,103 @@ fn click_play_button(
     }
 }
 
+pub fn spawn_text_input<'w, 's, 'a>(
+    parent: &'a mut ChildBuilder<'w, 's, '_>,
+    width: Val,
+    text_input: TextInput,
+) -> EntityCommands<'w, 's, 'a> {
+    let button_colors = ButtonColors::default();
+    let mut entity_commands = parent.spawn((
+            ButtonBundle {
+                style: Style {
+                    width,
+                    min_height: Val::Px(36.0),
+                    align_items: AlignItems::Center,
+                    padding: UiRect::horizontal(Val::Px(8.0)),
+                    margin: UiRect::top(Val::Px(5.0)),
+                    ..default()
+                },
+                background_color: button_colors.normal.into(),
+                ..default()
+            },
+            button_colors,
+        ));
+
+    entity_commands
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                text_input.placeholder,
+                TextStyle {
+                    font_size: 20.0,
+                    color: Color::GRAY,
+                    ..default()
+                },
+            ));
+        })
+        .insert(text_input);
+
+    entity_commands
+}
+
+fn focus_text_inputs(
+    mouse_input: Res<Input<MouseButton>>,
+    mut text_inputs: Query<(&Interaction, &mut TextInput)>,
+) {
+    if !mouse_input.just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    for (interaction, mut text_input) in &mut text_inputs {
+        let focused = *interaction == Interaction::Pressed;
+        if text_input.focused != focused {
+            text_input.focused = focused;
+        }
+    }
+}
+
+fn type_into_text_inputs(
+    mut received_characters: EventReader<ReceivedCharacter>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut text_inputs: Query<(&mut TextInput, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    let characters: Vec<char> = received_characters.read().map(|event| event.char).collect();
+
+    for (mut text_input, children) in &mut text_inputs {
+        if text_input.focused {
+            if keyboard_input.just_pressed(KeyCode::Back) {
+                text_input.value.pop();
+            }
+
+            for character in characters.iter() {
+                if (text_input.accepts)(*character) && text_input.value.chars().count() < text_input.max_length {
+                    text_input.value.push(*character);
+                }
+            }
+        }
+
+        if !text_input.is_changed() {
+            continue;
+        }
+
+        for child in children.iter() {
+            if let Ok(mut text) = texts.get_mut(*child) {
+                let section = &mut text.sections[0];
+                if text_input.value.is_empty() && !text_input.focused {
+                    section.value = text_input.placeholder.to_string();
+                    section.style.color = Color::GRAY;
+                } else {
+                    section.value = text_input.value.clone();
+                    if text_input.focused {
+                        section.value.push('|');
+                    }
+                    section.style.color = Color::rgb(0.9, 0.9, 0.9);
+                }
+            }
+        }
+    }
+}
+
 fn cleanup_menu(mut commands: Commands, menu: Query<Entity, With<Menu>>) {
     for entity in menu.iter() {
         commands.entity(entity).despawn_recursive();
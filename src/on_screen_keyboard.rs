@@ -0,0 +1,286 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::{GameState, PauseState};
+use crate::game::word_style;
+use crate::replay::{is_live, start_replay};
+use crate::seed::RunSeed;
+use crate::settings::Settings;
+use crate::sorting::with_bin_keys;
+use crate::target_lock::trash_at;
+use crate::trash::{Trash, TrashActionActive};
+use crate::trash_text::TrashText;
+use crate::typing::{KeyEdit, TypingKey, read_typing_keys, shifted};
+use crate::words::{AvailableWords, build_available_words};
+
+pub struct OnScreenKeyboardPlugin;
+
+const LETTER_ROWS: [&str; 3] = ["qwertyuiop", "asdfghjkl", "zxcvbnm"];
+const MAX_KEYS_PER_ROW: usize = 10;
+const KEY_HEIGHT: f32 = 44.0;
+// See-through so the trash piling up behind the keyboard stays visible
+const KEY_COLOR: Color = Color::rgba(0.15, 0.15, 0.15, 0.7);
+const PRESSED_KEY_COLOR: Color = Color::rgba(0.35, 0.35, 0.35, 0.9);
+const SHIFTED_KEY_COLOR: Color = Color::rgba(0.15, 0.35, 0.6, 0.9);
+
+
+#[derive(Component)]
+pub struct OnScreenKeyboard;
+
+#[derive(Component, Clone, Copy, PartialEq)]
+enum OnScreenKey {
+    Character(char),
+    Shift,
+    Backspace,
+    Clear,
+    Pause,
+}
+
+/// The next character key is typed as a capital
+#[derive(Resource, Default)]
+struct KeyboardShift(bool);
+
+
+/// A keyboard drawn over the bottom of the playfield for touch screens, it sends the same keys as a physical one.
+/// Tapping a falling trash locks it as the target.
+impl Plugin for OnScreenKeyboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<KeyboardShift>()
+        .add_systems(OnEnter(GameState::Playing), spawn_on_screen_keyboard
+            .after(start_replay)
+            .after(build_available_words)
+            .run_if(is_live))
+        .add_systems(OnExit(GameState::Playing), cleanup_on_screen_keyboard)
+        .add_systems(Update, (
+                // Before the keyboard is read so the keys are recorded on the frame they're typed
+                press_on_screen_keys.before(read_typing_keys)
+                    .run_if(in_state(GameState::Playing)).run_if(in_state(PauseState::Running)).run_if(is_live),
+                tap_trash.before(read_typing_keys)
+                    .run_if(in_state(GameState::Playing)).run_if(in_state(PauseState::Running)).run_if(is_live)
+                    .run_if(on_screen_keyboard_enabled),
+                update_on_screen_keys.after(press_on_screen_keys).run_if(in_state(GameState::Playing)),
+            ));
+    }
+}
+
+
+impl OnScreenKey {
+    fn label(&self, shift: bool) -> String {
+        match self {
+            // Phrases have spaces
+            Self::Character(' ') => "Space".to_string(),
+            Self::Character(character) => shifted(*character, shift).to_string(),
+            Self::Shift => "Shift".to_string(),
+            Self::Backspace => "Del".to_string(),
+            Self::Clear => "Clear".to_string(),
+            Self::Pause => "Pause".to_string(),
+        }
+    }
+
+    fn width(&self) -> f32 {
+        match self {
+            Self::Character(' ') => 1.5,
+            Self::Character(_) => 1.0,
+            _ => 1.5,
+        }
+    }
+}
+
+
+pub fn spawn_on_screen_keyboard(
+    mut commands: Commands,
+    mut shift: ResMut<KeyboardShift>,
+    settings: Res<Settings>,
+    run_seed: Res<RunSeed>,
+    available_words: Res<AvailableWords>,
+) {
+    shift.0 = false;
+    if !settings.on_screen_keyboard {
+        return;
+    }
+
+    let extra_characters = with_bin_keys(
+        available_words.extra_characters(word_style(&settings, &run_seed)),
+        &settings,
+        &run_seed,
+    );
+    let mut rows: Vec<Vec<OnScreenKey>> = extra_characters
+        .chunks(MAX_KEYS_PER_ROW)
+        .map(|characters| characters.iter().map(|character| OnScreenKey::Character(*character)).collect())
+        .collect();
+    for (index, letters) in LETTER_ROWS.iter().enumerate() {
+        let mut row: Vec<OnScreenKey> = letters.chars().map(OnScreenKey::Character).collect();
+        if index == LETTER_ROWS.len() - 1 {
+            row.insert(0, OnScreenKey::Shift);
+            row.push(OnScreenKey::Backspace);
+        }
+        rows.push(row);
+    }
+    rows.push(vec![OnScreenKey::Pause, OnScreenKey::Clear]);
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(0.0),
+                    width: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(2.0)),
+                    ..default()
+                },
+                z_index: ZIndex::Global(5),
+                ..default()
+            },
+            OnScreenKeyboard,
+        ))
+        .with_children(|parent| {
+            for row in rows {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Row,
+                            justify_content: JustifyContent::Center,
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|row_parent| {
+                        for key in row {
+                            spawn_key(row_parent, key);
+                        }
+                    });
+            }
+        });
+}
+
+fn spawn_key(parent: &mut ChildBuilder, key: OnScreenKey) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    // A tenth of the width per character key, so the longest row fills the screen
+                    width: Val::Percent(key.width() * 100.0 / MAX_KEYS_PER_ROW as f32 - 1.0),
+                    height: Val::Px(KEY_HEIGHT),
+                    margin: UiRect::all(Val::Percent(0.5)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: KEY_COLOR.into(),
+                ..default()
+            },
+            key,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                key.label(false),
+                TextStyle {
+                    font_size: 24.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn press_on_screen_keys(
+    mut shift: ResMut<KeyboardShift>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+    mut typing_keys: EventWriter<TypingKey>,
+    interaction_query: Query<(&Interaction, &OnScreenKey), Changed<Interaction>>,
+) {
+    for (interaction, key) in interaction_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match key {
+            OnScreenKey::Character(character) => {
+                typing_keys.send(KeyEdit::Letter(shifted(*character, shift.0)).into());
+                if shift.0 {
+                    shift.0 = false;
+                }
+            },
+            OnScreenKey::Shift => shift.0 = !shift.0,
+            OnScreenKey::Backspace => typing_keys.send(KeyEdit::Backspace.into()),
+            OnScreenKey::Clear => typing_keys.send(KeyEdit::Clear.into()),
+            OnScreenKey::Pause => next_pause_state.set(PauseState::Paused),
+        }
+    }
+}
+
+/// Highlights the pressed keys and the shift key, and switches the labels between lowercase and capitals
+fn update_on_screen_keys(
+    shift: Res<KeyboardShift>,
+    mut key_query: Query<(Ref<Interaction>, &OnScreenKey, &mut BackgroundColor, &Children)>,
+    mut text_query: Query<&mut Text>,
+) {
+    for (interaction, key, mut background_color, children) in key_query.iter_mut() {
+        if !interaction.is_changed() && !shift.is_changed() {
+            continue;
+        }
+
+        *background_color = if *interaction == Interaction::Pressed {
+            PRESSED_KEY_COLOR
+        } else if *key == OnScreenKey::Shift && shift.0 {
+            SHIFTED_KEY_COLOR
+        } else {
+            KEY_COLOR
+        }.into();
+
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.sections[0].value = key.label(shift.0);
+            }
+        }
+    }
+}
+
+/// Without the on-screen keyboard a click on the playfield is just a click, it doesn't lock a target
+fn on_screen_keyboard_enabled(settings: Res<Settings>) -> bool {
+    settings.on_screen_keyboard
+}
+
+/// A touch (or a click) on a falling trash locks it as the target
+#[allow(clippy::too_many_arguments)]
+fn tap_trash(
+    touches: Res<Touches>,
+    mouse_input: Res<Input<MouseButton>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    key_query: Query<&Interaction, With<OnScreenKey>>,
+    trash_text_query: Query<(&Parent, &TrashText)>,
+    trash_query: Query<(&Transform, &Trash), With<TrashActionActive>>,
+    mut typing_keys: EventWriter<TypingKey>,
+) {
+    // Typing on the keyboard isn't a tap on the trash behind it
+    if key_query.iter().any(|interaction| *interaction != Interaction::None) {
+        return;
+    }
+
+    let Ok((camera, camera_transform)) = camera_query.get_single() else {
+        return;
+    };
+
+    let clicked = window_query.get_single().ok()
+        .filter(|_| mouse_input.just_pressed(MouseButton::Left))
+        .and_then(|window| window.cursor_position());
+    let taps = touches.iter_just_pressed().map(|touch| touch.position()).chain(clicked);
+
+    for tap in taps {
+        let Some(position) = camera.viewport_to_world_2d(camera_transform, tap) else {
+            continue;
+        };
+
+        if trash_at(position, &trash_text_query, &trash_query).is_some() {
+            typing_keys.send(KeyEdit::Target { x: position.x, y: position.y }.into());
+        }
+    }
+}
+
+fn cleanup_on_screen_keyboard(mut commands: Commands, keyboard: Query<Entity, With<OnScreenKeyboard>>) {
+    for entity in keyboard.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
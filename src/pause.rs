@@ -0,0 +1,172 @@
+use bevy::prelude::*;
+use bevy_rapier2d::plugin::RapierConfiguration;
+
+use crate::{GameState, PauseState};
+use crate::events::RunEnded;
+use crate::menu::ButtonColors;
+use crate::target_lock::{TargetLock, lock_target};
+
+pub struct PausePlugin;
+
+#[derive(Component)]
+struct PauseMenu;
+
+#[derive(Component, Clone, Copy)]
+enum PauseButton {
+    Resume,
+    Restart,
+    EndRun,
+    QuitToMenu,
+}
+
+
+/// Esc pauses the run, the simulation, the timers and the typing are frozen until it's resumed
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(PauseState::Paused), (freeze_physics, spawn_pause_menu))
+        .add_systems(OnExit(PauseState::Paused), (unfreeze_physics, cleanup_pause_menu))
+        .add_systems(OnExit(GameState::Playing), resume)
+        .add_systems(OnEnter(GameState::Restarting), restart)
+        .add_systems(Update, (
+                toggle_pause.before(lock_target).run_if(in_state(GameState::Playing)),
+                click_pause_buttons.run_if(in_state(PauseState::Paused)),
+            ));
+    }
+}
+
+
+fn toggle_pause(
+    keyboard_input: Res<Input<KeyCode>>,
+    target_lock: Res<TargetLock>,
+    pause_state: Res<State<PauseState>>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+) {
+    // With a locked target Esc releases the lock instead
+    if !keyboard_input.just_pressed(KeyCode::Escape) || target_lock.target.is_some() {
+        return;
+    }
+
+    match pause_state.get() {
+        PauseState::Running => next_pause_state.set(PauseState::Paused),
+        PauseState::Paused => next_pause_state.set(PauseState::Running),
+    }
+}
+
+fn resume(mut next_pause_state: ResMut<NextState<PauseState>>) {
+    next_pause_state.set(PauseState::Running);
+}
+
+// Entering `GameState::Playing` from itself doesn't run the state transition,
+// so a restart goes through `GameState::Restarting` which exits `Playing` and enters it back.
+fn restart(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::Playing);
+}
+
+fn freeze_physics(mut rapier_configuration: ResMut<RapierConfiguration>) {
+    rapier_configuration.physics_pipeline_active = false;
+}
+
+fn unfreeze_physics(mut rapier_configuration: ResMut<RapierConfiguration>) {
+    rapier_configuration.physics_pipeline_active = true;
+}
+
+fn spawn_pause_menu(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            PauseMenu,
+        ))
+        .with_children(|children| {
+            children.spawn(TextBundle::from_section(
+                "Paused",
+                TextStyle {
+                    font_size: 80.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+
+            for (label, button) in [
+                ("Resume", PauseButton::Resume),
+                ("Restart", PauseButton::Restart),
+                ("End run", PauseButton::EndRun),
+                ("Quit to menu", PauseButton::QuitToMenu),
+            ] {
+                let button_colors = ButtonColors::default();
+                children
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                width: Val::Px(240.0),
+                                height: Val::Px(50.0),
+                                margin: UiRect::top(Val::Px(15.0)),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            background_color: button_colors.normal.into(),
+                            ..default()
+                        },
+                        button_colors,
+                        button,
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            label,
+                            TextStyle {
+                                font_size: 35.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                ..default()
+                            },
+                        ));
+                    });
+            }
+        });
+}
+
+fn click_pause_buttons(
+    mut next_state: ResMut<NextState<GameState>>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+    mut run_ended_events: EventWriter<RunEnded>,
+    mut interaction_query: Query<
+        (&Interaction, &mut BackgroundColor, &ButtonColors, &PauseButton),
+        Changed<Interaction>,
+    >,
+) {
+    for (interaction, mut color, button_colors, button) in &mut interaction_query {
+        match *interaction {
+            Interaction::Pressed => match button {
+                PauseButton::Resume => next_pause_state.set(PauseState::Running),
+                PauseButton::Restart => next_state.set(GameState::Restarting),
+                // Goes to the game over screen so the run is scored, the only way a Zen run ends
+                PauseButton::EndRun => run_ended_events.send(RunEnded),
+                PauseButton::QuitToMenu => next_state.set(GameState::Menu),
+            },
+            Interaction::Hovered => {
+                *color = button_colors.hovered.into();
+            }
+            Interaction::None => {
+                *color = button_colors.normal.into();
+            }
+        }
+    }
+}
+
+fn cleanup_pause_menu(mut commands: Commands, pause_menu: Query<Entity, With<PauseMenu>>) {
+    for entity in pause_menu.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,201 @@
+use std::path::PathBuf;
+
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext, io::Reader};
+use bevy::prelude::*;
+use bevy::reflect::TypePath;
+use bevy::utils::BoxedFuture;
+use rand::Rng;
+use rand::seq::SliceRandom;
+use thiserror::Error;
+
+use crate::loading::DataAssets;
+use crate::seed::RunSeed;
+use crate::words::{AvailableWord, AvailableWords, RunWordSources, WordSources, is_free};
+
+const DEFAULT_PHRASE_LIST: &str = "quotes";
+// Longer phrases don't fit on a trash even when wrapped
+pub const MAX_PHRASE_LENGTH: usize = 60;
+
+
+/// Short quotes and sentences for `GameMode::Phrases`, loaded from a `.phrases` file with one phrase per line
+#[derive(Asset, TypePath, Debug)]
+pub struct PhraseList {
+    pub name: String,
+    pub phrases: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct PhraseListLoader;
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum PhraseListLoaderError {
+    #[error("Could not read the phrase list: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("The phrase list doesn't contain any valid phrase")]
+    Empty,
+}
+
+
+/// Phrases can use any character a word can, with single spaces between the words
+pub fn is_valid_phrase(phrase: &str) -> bool {
+    !phrase.is_empty()
+        && phrase.chars().count() <= MAX_PHRASE_LENGTH
+        && phrase.chars().all(|character| character == ' ' || (!character.is_whitespace() && !character.is_control()))
+        && !phrase.starts_with(' ')
+        && !phrase.ends_with(' ')
+        && !phrase.contains("  ")
+}
+
+/// Splits the text into phrases, every line is a phrase and runs of whitespace become a single space.
+/// Returns the valid phrases and the amount of lines that were rejected (see `is_valid_phrase`).
+pub fn parse_phrases(text: &str) -> (Vec<String>, usize) {
+    let mut phrases = Vec::new();
+    let mut rejected = 0;
+
+    for line in text.split('\n') {
+        let phrase = line.split_whitespace().collect::<Vec<&str>>().join(" ");
+        if phrase.is_empty() {
+            continue;
+        }
+
+        if is_valid_phrase(&phrase) {
+            phrases.push(phrase);
+        } else {
+            rejected += 1;
+        }
+    }
+
+    (phrases, rejected)
+}
+
+
+impl AssetLoader for PhraseListLoader {
+    type Asset = PhraseList;
+    type Settings = ();
+    type Error = PhraseListLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut text = String::new();
+            reader.read_to_string(&mut text).await?;
+
+            let (phrases, rejected) = parse_phrases(&text);
+            if rejected > 0 {
+                warn!("Skipped {rejected} invalid phrases in {:?}", load_context.path());
+            }
+            if phrases.is_empty() {
+                return Err(PhraseListLoaderError::Empty);
+            }
+
+            let name = load_context
+                .path()
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+            Ok(PhraseList { name, phrases })
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["phrases"]
+    }
+}
+
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_phrase_file(path: &PathBuf) -> Vec<String> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => {
+            let (phrases, rejected) = parse_phrases(&text);
+            if rejected > 0 {
+                warn!("Skipped {rejected} invalid phrases in {path:?}");
+            }
+            phrases
+        },
+        Err(error) => {
+            warn!("Failed to read phrase list {path:?}: {error}");
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn read_phrase_file(_path: &PathBuf) -> Vec<String> {
+    Vec::new()
+}
+
+
+/// The phrases of a phrase run, the bundled lists and the `--phrases` files.
+/// Daily runs only use the default list so everyone gets the same phrases.
+pub fn build_available_phrases(
+    word_sources: &WordSources,
+    run_seed: &RunSeed,
+    data_assets: &DataAssets,
+    phrase_lists: &Assets<PhraseList>,
+) -> (RunWordSources, AvailableWords) {
+    let lists: Vec<&PhraseList> = data_assets.phrase_lists.iter()
+        .filter_map(|handle| phrase_lists.get(handle))
+        .collect();
+
+    let run_phrase_sources = if run_seed.is_daily() {
+        RunWordSources {
+            packs: vec![DEFAULT_PHRASE_LIST.to_string()],
+            extra_words: Vec::new(),
+        }
+    } else {
+        RunWordSources {
+            packs: lists.iter().map(|list| list.name.clone()).collect(),
+            extra_words: word_sources.extra_phrases(),
+        }
+    };
+
+    let mut phrases: Vec<String> = Vec::new();
+    for list in lists.iter().filter(|list| run_phrase_sources.packs.contains(&list.name)) {
+        phrases.extend(list.phrases.iter().cloned());
+    }
+    phrases.extend(run_phrase_sources.extra_words.iter().cloned());
+
+    (run_phrase_sources, AvailableWords::from_words(phrases))
+}
+
+/// Picks a phrase for a new trash, `difficulty` goes from 0 (only the shortest phrases) to 1 (any phrase).
+/// Like words, phrases that clash with one of the `taken_words` are skipped.
+pub fn choose_phrase(
+    available_phrases: &AvailableWords,
+    difficulty: f32,
+    taken_words: &[String],
+    random: &mut impl Rng,
+) -> Option<String> {
+    let mut free: Vec<&AvailableWord> = available_phrases.0.iter()
+        .filter(|phrase| is_free(&phrase.word, taken_words))
+        .collect();
+    free.sort_by_key(|phrase| phrase.word.chars().count());
+
+    let share = 0.3 + 0.7 * difficulty.clamp(0.0, 1.0);
+    let candidates = ((free.len() as f32 * share).ceil() as usize).min(free.len());
+
+    free[..candidates].choose(random).map(|phrase| phrase.word.clone())
+}
+
+/// Phrase lists outside of the assets can be added with `--phrases <path>`
+pub fn phrase_files_from_arguments() -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut arguments = std::env::args().skip(1);
+
+    while let Some(argument) = arguments.next() {
+        if argument == "--phrases" {
+            if let Some(path) = arguments.next() {
+                files.push(PathBuf::from(path));
+            }
+        }
+    }
+
+    files
+}
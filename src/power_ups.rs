@@ -0,0 +1,260 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use rand::Rng;
+use rand::distributions::WeightedIndex;
+use rand::prelude::Distribution;
+
+use crate::{GameState, PauseState};
+use crate::events::PowerUpTriggered;
+use crate::game_over::GameOverLine;
+use crate::replay::physics_timestep;
+use crate::trash::{Trash, TrashActionActive, handle_trash_collision};
+
+pub struct PowerUpsPlugin;
+
+const SLOW_MO_TIME_SCALE: f32 = 0.4;
+// Slow enough for `clamp_duplicated_trash` to leave the pile alone
+const MAGNET_PULL: Vec2 = Vec2::new(15.0, 40.0);
+const LINE_COLOR: Color = Color::WHITE;
+const SHIELDED_LINE_COLOR: Color = Color::CYAN;
+
+
+/// Adding a power up takes a variant here, its entries in the `impl` below and a system for its effect
+/// (the explosion has its own module)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum PowerUp {
+    #[default]
+    None,
+    /// Blows up the trash around it when it lands
+    Explosion,
+    /// Spawns in a group, typing one word clears all of it
+    Link,
+    /// Halts the falling trash
+    Freeze,
+    /// Slows the physics down
+    SlowMo,
+    /// Pulls the pile down and packs it
+    Magnet,
+    /// Absorbs the next contact with the line
+    Shield,
+}
+
+/// When a power up goes off
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PowerUpTrigger {
+    /// As soon as its word is typed, the trash is cleared
+    WordCompleted,
+    /// When the trash hits something after its word was typed
+    Impact,
+}
+
+/// The power ups with a duration that are running, and the shields left
+#[derive(Resource, Default)]
+pub struct ActivePowerUps {
+    timers: Vec<(PowerUp, Timer)>,
+    pub shields: u32,
+}
+
+/// A trash halted by `PowerUp::Freeze`, with the velocity it gets back
+#[derive(Component)]
+struct Frozen(Velocity);
+
+
+impl Plugin for PowerUpsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActivePowerUps>()
+        .add_systems(OnEnter(GameState::Playing), reset_power_ups)
+        .add_systems(OnExit(GameState::Playing), (reset_power_ups, slow_down_physics).chain())
+        .add_systems(Update, (
+                (
+                    start_power_ups,
+                    tick_power_ups,
+                    freeze_falling_trash,
+                    slow_down_physics,
+                    pull_pile_down,
+                    tint_shielded_line,
+                ).chain().after(handle_trash_collision)
+                    .run_if(in_state(GameState::Playing)).run_if(in_state(PauseState::Running)),
+                tint_power_up_trash.run_if(in_state(GameState::Playing)),
+            ));
+    }
+}
+
+
+impl PowerUp {
+    pub const ALL: [PowerUp; 6] = [
+        PowerUp::Explosion,
+        PowerUp::Link,
+        PowerUp::Freeze,
+        PowerUp::SlowMo,
+        PowerUp::Magnet,
+        PowerUp::Shield,
+    ];
+
+    /// Picks a power up according to the spawn weights
+    pub fn choose(random: &mut impl Rng) -> Self {
+        let weights = WeightedIndex::new(Self::ALL.iter().map(PowerUp::spawn_weight))
+            .expect("the power ups have positive spawn weights");
+
+        Self::ALL[weights.sample(random)]
+    }
+
+    fn spawn_weight(&self) -> f32 {
+        match self {
+            Self::None => 0.0,
+            Self::Explosion | Self::Link => 1.0,
+            Self::Freeze | Self::SlowMo | Self::Magnet | Self::Shield => 0.5,
+        }
+    }
+
+    /// The color the trash pulses with
+    fn tint(&self) -> Option<Color> {
+        match self {
+            Self::None => None,
+            Self::Explosion => Some(Color::rgb(1.0, 0.0, 0.0)),
+            Self::Link => Some(Color::rgb(0.0, 0.0, 1.0)),
+            Self::Freeze => Some(Color::rgb(0.0, 1.0, 1.0)),
+            Self::SlowMo => Some(Color::rgb(0.6, 0.0, 1.0)),
+            Self::Magnet => Some(Color::rgb(1.0, 0.5, 0.0)),
+            Self::Shield => Some(Color::rgb(1.0, 1.0, 0.0)),
+        }
+    }
+
+    pub fn trigger(&self) -> PowerUpTrigger {
+        match self {
+            Self::Explosion => PowerUpTrigger::Impact,
+            _ => PowerUpTrigger::WordCompleted,
+        }
+    }
+
+    /// How long the effect lasts, the others are instant
+    fn duration(&self) -> Option<Duration> {
+        match self {
+            Self::Freeze => Some(Duration::from_secs(4)),
+            Self::SlowMo => Some(Duration::from_secs(5)),
+            Self::Magnet => Some(Duration::from_secs(3)),
+            _ => None,
+        }
+    }
+}
+
+impl ActivePowerUps {
+    pub fn is_active(&self, power_up: PowerUp) -> bool {
+        self.timers.iter().any(|(active, _)| *active == power_up)
+    }
+}
+
+
+fn reset_power_ups(mut active_power_ups: ResMut<ActivePowerUps>) {
+    *active_power_ups = ActivePowerUps::default();
+}
+
+fn start_power_ups(
+    mut active_power_ups: ResMut<ActivePowerUps>,
+    mut power_up_triggered_events: EventReader<PowerUpTriggered>,
+) {
+    for triggered in power_up_triggered_events.read() {
+        if triggered.power_up == PowerUp::Shield {
+            active_power_ups.shields += 1;
+        }
+
+        let Some(duration) = triggered.power_up.duration() else {
+            continue;
+        };
+        // Triggering a running power up starts it over
+        active_power_ups.timers.retain(|(active, _)| *active != triggered.power_up);
+        active_power_ups.timers.push((triggered.power_up, Timer::new(duration, TimerMode::Once)));
+    }
+}
+
+fn tick_power_ups(mut active_power_ups: ResMut<ActivePowerUps>, time: Res<Time>) {
+    for (_, timer) in active_power_ups.timers.iter_mut() {
+        timer.tick(time.delta());
+    }
+    active_power_ups.timers.retain(|(_, timer)| !timer.finished());
+}
+
+#[allow(clippy::type_complexity)]
+fn freeze_falling_trash(
+    mut commands: Commands,
+    active_power_ups: Res<ActivePowerUps>,
+    mut falling_trash_query: Query<(Entity, &mut Velocity), (With<TrashActionActive>, Without<Frozen>)>,
+    frozen_trash_query: Query<(Entity, &Frozen)>,
+) {
+    if active_power_ups.is_active(PowerUp::Freeze) {
+        for (entity, mut velocity) in falling_trash_query.iter_mut() {
+            commands.entity(entity).insert((Frozen(*velocity), GravityScale(0.0)));
+            *velocity = Velocity::zero();
+        }
+        return;
+    }
+
+    for (entity, frozen) in frozen_trash_query.iter() {
+        commands.entity(entity).insert(frozen.0).remove::<(Frozen, GravityScale)>();
+    }
+}
+
+fn slow_down_physics(
+    active_power_ups: Res<ActivePowerUps>,
+    mut rapier_configuration: ResMut<RapierConfiguration>,
+) {
+    let time_scale = if active_power_ups.is_active(PowerUp::SlowMo) { SLOW_MO_TIME_SCALE } else { 1.0 };
+    let timestep_mode = physics_timestep(time_scale);
+
+    if rapier_configuration.timestep_mode != timestep_mode {
+        rapier_configuration.timestep_mode = timestep_mode;
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn pull_pile_down(
+    active_power_ups: Res<ActivePowerUps>,
+    mut pile_query: Query<(&Transform, &mut Velocity), (With<Trash>, Without<TrashActionActive>)>,
+) {
+    if !active_power_ups.is_active(PowerUp::Magnet) {
+        return;
+    }
+
+    // Down and toward the middle, where the pile is the lowest
+    for (transform, mut velocity) in pile_query.iter_mut() {
+        velocity.linvel = Vec2::new(-transform.translation.x.signum() * MAGNET_PULL.x, -MAGNET_PULL.y);
+    }
+}
+
+fn tint_shielded_line(
+    active_power_ups: Res<ActivePowerUps>,
+    mut line_query: Query<&mut Sprite, With<GameOverLine>>,
+) {
+    let color = if active_power_ups.shields > 0 { SHIELDED_LINE_COLOR } else { LINE_COLOR };
+    for mut sprite in line_query.iter_mut() {
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+}
+
+fn tint_power_up_trash(
+    mut active_trash_query: Query<(&Trash, &mut Sprite), With<TrashActionActive>>,
+    mut inactive_trash_query: Query<&mut Sprite, (With<Trash>, Without<TrashActionActive>)>,
+    time: Res<Time>,
+) {
+    let pulse = (3.5 * time.elapsed_seconds()).sin() / 2.0 + 0.5;
+
+    for (trash, mut sprite) in active_trash_query.iter_mut() {
+        if let Some(tint) = trash.power_up.tint() {
+            sprite.color = Color::rgb(
+                tint.r() + (1.0 - tint.r()) * pulse,
+                tint.g() + (1.0 - tint.g()) * pulse,
+                tint.b() + (1.0 - tint.b()) * pulse,
+            );
+        }
+    }
+
+    for mut sprite in inactive_trash_query.iter_mut() {
+        if sprite.color != Color::WHITE {
+            sprite.color = Color::WHITE;
+        }
+    }
+}
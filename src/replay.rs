@@ -0,0 +1,483 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::time::{TimeSystem, TimeUpdateStrategy};
+use bevy::utils::{HashSet, Instant};
+use bevy::window::PrimaryWindow;
+use bevy_rapier2d::plugin::{RapierConfiguration, TimestepMode};
+use serde::{Deserialize, Serialize};
+
+use crate::{GameState, PauseState};
+use crate::difficulty::{DifficultyPreset, adaptive_difficulty, difficulty_preset};
+use crate::game::{slow_spawns, starting_difficulty, word_length_limits, word_style};
+use crate::game_mode::GameMode;
+use crate::game_over::GameOver;
+use crate::menu::ButtonColors;
+use crate::save;
+use crate::seed::{RunSeed, SeedSettings, seed_run};
+use crate::settings::{KeyboardLayout, Settings};
+use crate::sorting::sorting_bins;
+use crate::target_lock::TargetLock;
+use crate::typing::{TypingKey, read_typing_keys, typing};
+use crate::versus::read_versus_keys;
+use crate::words::{RunWordSources, WordSources, WordStyle, build_available_words};
+
+pub struct ReplayPlugin;
+
+/// How much a frame advances the game when there's no window to time it (the headless tests)
+pub const SIMULATION_STEP: f32 = 1.0 / 60.0;
+// The longest frame a replay keeps, a longer one (a window drag, a breakpoint) is played as this long
+const MAX_FRAME_DURATION: Duration = Duration::from_millis(250);
+const REPLAY_VERSION: u32 = 7;
+const LAST_RUN_SAVE_NAME: &str = "last_run";
+
+
+/// The seed, the config and the typed keys of a run, enough to play it again
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u64,
+    #[serde(default)]
+    pub mode: GameMode,
+    pub starting_difficulty: u32,
+    #[serde(default)]
+    pub difficulty: DifficultyPreset,
+    #[serde(default)]
+    pub adaptive_difficulty: bool,
+    pub min_word_length: usize,
+    pub max_word_length: usize,
+    #[serde(default)]
+    pub word_style: WordStyle,
+    pub target_lock: bool,
+    #[serde(default)]
+    pub sorting_bins: bool,
+    #[serde(default)]
+    pub gamepad_typing: bool,
+    /// Which keys belong to which player of a versus run
+    #[serde(default)]
+    pub keyboard_layout: KeyboardLayout,
+    pub word_packs: Vec<String>,
+    pub extra_words: Vec<String>,
+    /// The simulation frame each key was typed on
+    pub keys: Vec<(u32, TypingKey)>,
+    /// How long every simulation frame was in microseconds, the replay goes through the same frames
+    #[serde(default)]
+    pub frame_durations: Vec<u32>,
+}
+
+/// The replay the "Watch replay" buttons play, the `--replay` file or the last run
+#[derive(Resource, Default)]
+pub struct LoadedReplay(pub Option<Replay>);
+
+/// Frames simulated since the run started, pauses excluded
+#[derive(Resource, Default)]
+pub struct SimulationFrame(pub u32);
+
+#[derive(Resource, Default)]
+pub struct ReplayPlayback {
+    /// Set by the "Watch replay" buttons, the next run plays `LoadedReplay`
+    pub requested: bool,
+    active: Option<Playback>,
+}
+
+struct Playback {
+    replay: Replay,
+    next_key: usize,
+    /// The player's own config, put back once the replay is over
+    stashed: (Settings, SeedSettings, WordSources, bool, GameMode),
+}
+
+#[derive(Resource, Default)]
+struct Recording(Replay);
+
+#[derive(Component)]
+struct ReplayMenu;
+
+#[derive(Component)]
+struct WatchReplayButton;
+
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(SIMULATION_STEP)))
+        .insert_resource(RapierConfiguration {
+            timestep_mode: physics_timestep(1.0),
+            ..default()
+        })
+        .insert_resource(LoadedReplay(load_replay()))
+        .init_resource::<SimulationFrame>()
+        .init_resource::<ReplayPlayback>()
+        .init_resource::<Recording>()
+        .add_systems(OnEnter(GameState::Playing), (
+                start_replay.before(seed_run),
+                start_recording.after(build_available_words),
+            ))
+        .add_systems(OnEnter(GameState::GameOver), save_recording.run_if(is_live))
+        .add_systems(OnEnter(GameState::Menu), spawn_replay_menu)
+        .add_systems(OnEnter(GameState::GameOver), spawn_game_over_replay_button)
+        .add_systems(OnExit(GameState::GameOver), end_replay)
+        .add_systems(OnTransition { from: GameState::Playing, to: GameState::Menu }, end_replay)
+        .add_systems(OnExit(GameState::Menu), cleanup_replay_menu)
+        .add_systems(Update, (
+                advance_simulation_frame.before(read_typing_keys).before(read_versus_keys).before(feed_replay_keys)
+                    .run_if(in_state(GameState::Playing)).run_if(in_state(PauseState::Running)),
+                record_typing_keys.after(read_typing_keys).after(read_versus_keys)
+                    .run_if(in_state(GameState::Playing)).run_if(in_state(PauseState::Running)).run_if(is_live),
+                record_frame_duration.after(advance_simulation_frame)
+                    .run_if(in_state(GameState::Playing)).run_if(in_state(PauseState::Running)).run_if(is_live),
+                feed_replay_keys.before(typing)
+                    .run_if(in_state(GameState::Playing)).run_if(in_state(PauseState::Running)).run_if(not(is_live)),
+                click_watch_replay_button,
+            ))
+        .add_systems(First, set_frame_duration.before(TimeSystem));
+
+        // A replay goes through its frames as fast as the machine can render them, this keeps it at the recorded pace.
+        // The headless tests have no window and run as fast as they can
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_systems(Last, limit_replay_frame_rate.run_if(any_with_component::<PrimaryWindow>()).run_if(not(is_live)));
+    }
+}
+
+
+/// The physics always advance in steps of `SIMULATION_STEP`, as many as the time that went by holds,
+/// so they don't depend on the frame rate. `time_scale` slows them down
+pub fn physics_timestep(time_scale: f32) -> TimestepMode {
+    TimestepMode::Interpolated {
+        dt: SIMULATION_STEP,
+        time_scale,
+        substeps: 1,
+    }
+}
+
+
+/// Live runs read the keyboard, replays feed the recorded keys instead
+pub fn is_live(playback: Res<ReplayPlayback>) -> bool {
+    playback.active.is_none()
+}
+
+/// `--replay <path>` plays a shared replay file, otherwise the last run can be watched again
+fn load_replay() -> Option<Replay> {
+    let replay = replay_file_from_arguments().or_else(|| save::load::<Replay>(LAST_RUN_SAVE_NAME))?;
+    if replay.version != REPLAY_VERSION {
+        warn!("Ignoring replay made with version {}, expected {REPLAY_VERSION}", replay.version);
+        return None;
+    }
+
+    Some(replay)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn replay_file_from_arguments() -> Option<Replay> {
+    let mut arguments = std::env::args().skip(1);
+    while let Some(argument) = arguments.next() {
+        if argument == "--replay" {
+            let path = arguments.next()?;
+            return match std::fs::read_to_string(&path).map_err(|error| error.to_string())
+                .and_then(|text| ron::from_str(&text).map_err(|error| error.to_string())) {
+                Ok(replay) => Some(replay),
+                Err(error) => {
+                    warn!("Failed to read replay {path}: {error}");
+                    None
+                }
+            };
+        }
+    }
+
+    None
+}
+
+#[cfg(target_arch = "wasm32")]
+fn replay_file_from_arguments() -> Option<Replay> {
+    None
+}
+
+/// How far the next frame advances the game: the real time since the last frame in live runs
+/// (in whole microseconds so the recording has it exactly), the recorded duration in replays
+/// and `SIMULATION_STEP` without a window.
+/// This runs before the state transitions, a run that starts this frame is already the one to time.
+#[allow(clippy::too_many_arguments)]
+fn set_frame_duration(
+    mut time_update_strategy: ResMut<TimeUpdateStrategy>,
+    playback: Res<ReplayPlayback>,
+    loaded_replay: Res<LoadedReplay>,
+    simulation_frame: Res<SimulationFrame>,
+    state: Res<State<GameState>>,
+    next_state: Res<NextState<GameState>>,
+    window_query: Query<(), With<PrimaryWindow>>,
+    mut last_frame: Local<Option<Instant>>,
+) {
+    let now = Instant::now();
+    let step = Duration::from_secs_f32(SIMULATION_STEP);
+    let elapsed = last_frame.replace(now).map_or(step, |last_frame| now - last_frame);
+
+    // `start_replay` only picks the replay and resets the frame count once the run has started,
+    // this picks the same one: the replay restarted from the pause menu, the requested one or none
+    let starting_run = next_state.0 == Some(GameState::Playing);
+    let frame = if starting_run { 0 } else { simulation_frame.0 };
+    let replay = if !starting_run || *state.get() == GameState::Restarting {
+        playback.active.as_ref().map(|active| &active.replay)
+    } else if playback.requested {
+        loaded_replay.0.as_ref()
+    } else {
+        None
+    };
+
+    let duration = if let Some(replay) = replay {
+        // The frame about to be simulated, `advance_simulation_frame` counts it during the update
+        replay.frame_durations.get(frame as usize)
+            .map_or(step, |micros| Duration::from_micros(*micros as u64))
+    } else if window_query.is_empty() {
+        step
+    } else {
+        Duration::from_micros(elapsed.min(MAX_FRAME_DURATION).as_micros() as u64)
+    };
+
+    *time_update_strategy = TimeUpdateStrategy::ManualDuration(duration);
+}
+
+fn limit_replay_frame_rate(time_update_strategy: Res<TimeUpdateStrategy>, mut last_frame: Local<Option<Instant>>) {
+    let TimeUpdateStrategy::ManualDuration(frame_duration) = *time_update_strategy else {
+        return;
+    };
+    if let Some(last_frame) = *last_frame {
+        let elapsed = last_frame.elapsed();
+        if elapsed < frame_duration {
+            std::thread::sleep(frame_duration - elapsed);
+        }
+    }
+    *last_frame = Some(Instant::now());
+}
+
+/// Swaps the player's config for the one of the replay before the run is seeded
+#[allow(clippy::too_many_arguments)]
+pub fn start_replay(
+    mut playback: ResMut<ReplayPlayback>,
+    mut simulation_frame: ResMut<SimulationFrame>,
+    loaded_replay: Res<LoadedReplay>,
+    mut settings: ResMut<Settings>,
+    mut seed_settings: ResMut<SeedSettings>,
+    mut word_sources: ResMut<WordSources>,
+    mut target_lock: ResMut<TargetLock>,
+    mut game_mode: ResMut<GameMode>,
+) {
+    simulation_frame.0 = 0;
+
+    // Restarting from the pause menu watches the replay from the start again
+    if let Some(active) = playback.active.as_mut() {
+        active.next_key = 0;
+        return;
+    }
+
+    if !playback.requested {
+        return;
+    }
+    playback.requested = false;
+    let Some(replay) = loaded_replay.0.clone() else {
+        return;
+    };
+
+    let stashed = (settings.clone(), seed_settings.clone(), word_sources.clone(), target_lock.enabled, *game_mode);
+
+    settings.starting_difficulty = replay.starting_difficulty;
+    settings.difficulty = replay.difficulty;
+    settings.adaptive_difficulty = replay.adaptive_difficulty;
+    settings.min_word_length = replay.min_word_length;
+    settings.max_word_length = replay.max_word_length;
+    settings.fix_word_lengths();
+    settings.word_style = replay.word_style;
+    settings.sorting_bins = replay.sorting_bins;
+    settings.gamepad_typing = replay.gamepad_typing;
+    settings.keyboard_layout = replay.keyboard_layout;
+    *seed_settings = SeedSettings {
+        daily: false,
+        custom_seed: Some(replay.seed),
+    };
+    *word_sources = WordSources {
+        enabled_packs: replay.word_packs.iter().cloned().collect::<HashSet<String>>(),
+        files: Vec::new(),
+        custom_words: replay.extra_words.join(" "),
+        // Only the mode of the replay reads its extras, phrases are kept whole
+        phrase_files: Vec::new(),
+        custom_phrases: replay.extra_words.clone(),
+    };
+    target_lock.enabled = replay.target_lock;
+    *game_mode = replay.mode;
+
+    playback.active = Some(Playback {
+        replay,
+        next_key: 0,
+        stashed,
+    });
+}
+
+fn end_replay(
+    mut playback: ResMut<ReplayPlayback>,
+    mut settings: ResMut<Settings>,
+    mut seed_settings: ResMut<SeedSettings>,
+    mut word_sources: ResMut<WordSources>,
+    mut target_lock: ResMut<TargetLock>,
+    mut game_mode: ResMut<GameMode>,
+) {
+    let Some(active) = playback.active.take() else {
+        return;
+    };
+
+    (*settings, *seed_settings, *word_sources, target_lock.enabled, *game_mode) = active.stashed;
+}
+
+fn start_recording(
+    mut recording: ResMut<Recording>,
+    settings: Res<Settings>,
+    run_seed: Res<RunSeed>,
+    run_word_sources: Res<RunWordSources>,
+    target_lock: Res<TargetLock>,
+    game_mode: Res<GameMode>,
+) {
+    let length_limits = word_length_limits(&settings, &run_seed);
+    recording.0 = Replay {
+        version: REPLAY_VERSION,
+        seed: run_seed.seed,
+        mode: *game_mode,
+        starting_difficulty: starting_difficulty(&settings, &run_seed),
+        difficulty: difficulty_preset(&settings, &run_seed),
+        adaptive_difficulty: adaptive_difficulty(&settings, &run_seed),
+        min_word_length: *length_limits.start(),
+        max_word_length: *length_limits.end(),
+        word_style: word_style(&settings, &run_seed),
+        target_lock: target_lock.enabled,
+        sorting_bins: sorting_bins(&settings, &run_seed),
+        // The picker leaves the plain words of a daily run alone, so only the pace matters
+        gamepad_typing: slow_spawns(&settings, &run_seed),
+        keyboard_layout: settings.keyboard_layout,
+        word_packs: run_word_sources.packs.clone(),
+        extra_words: run_word_sources.extra_words.clone(),
+        keys: Vec::new(),
+        frame_durations: Vec::new(),
+    };
+}
+
+fn advance_simulation_frame(mut simulation_frame: ResMut<SimulationFrame>) {
+    simulation_frame.0 += 1;
+}
+
+fn record_typing_keys(
+    mut recording: ResMut<Recording>,
+    simulation_frame: Res<SimulationFrame>,
+    mut typing_keys: EventReader<TypingKey>,
+) {
+    for typing_key in typing_keys.read() {
+        recording.0.keys.push((simulation_frame.0, *typing_key));
+    }
+}
+
+fn record_frame_duration(mut recording: ResMut<Recording>, time: Res<Time>) {
+    recording.0.frame_durations.push(time.delta().as_micros() as u32);
+}
+
+fn feed_replay_keys(
+    mut playback: ResMut<ReplayPlayback>,
+    simulation_frame: Res<SimulationFrame>,
+    mut typing_keys: EventWriter<TypingKey>,
+) {
+    let Some(active) = playback.active.as_mut() else {
+        return;
+    };
+
+    while let Some((frame, typing_key)) = active.replay.keys.get(active.next_key) {
+        if *frame > simulation_frame.0 {
+            break;
+        }
+        typing_keys.send(*typing_key);
+        active.next_key += 1;
+    }
+}
+
+fn save_recording(recording: Res<Recording>, mut loaded_replay: ResMut<LoadedReplay>) {
+    save::save_compact(LAST_RUN_SAVE_NAME, &recording.0);
+    loaded_replay.0 = Some(recording.0.clone());
+}
+
+fn spawn_watch_replay_button(commands: &mut Commands, style: Style, bundle: impl Bundle) {
+    commands
+        .spawn((
+            NodeBundle {
+                style,
+                ..default()
+            },
+            bundle,
+        ))
+        .with_children(|children| {
+            let button_colors = ButtonColors::default();
+            children
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            height: Val::Px(40.0),
+                            padding: UiRect::horizontal(Val::Px(10.0)),
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: button_colors.normal.into(),
+                        ..default()
+                    },
+                    button_colors,
+                    WatchReplayButton,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Watch replay",
+                        TextStyle {
+                            font_size: 25.0,
+                            color: Color::rgb(0.9, 0.9, 0.9),
+                            ..default()
+                        },
+                    ));
+                });
+        });
+}
+
+fn spawn_replay_menu(mut commands: Commands, loaded_replay: Res<LoadedReplay>) {
+    if loaded_replay.0.is_none() {
+        return;
+    }
+
+    spawn_watch_replay_button(&mut commands, Style {
+        position_type: PositionType::Absolute,
+        width: Val::Percent(100.0),
+        top: Val::Percent(8.0),
+        justify_content: JustifyContent::Center,
+        ..default()
+    }, ReplayMenu);
+}
+
+fn spawn_game_over_replay_button(mut commands: Commands, loaded_replay: Res<LoadedReplay>) {
+    if loaded_replay.0.is_none() {
+        return;
+    }
+
+    spawn_watch_replay_button(&mut commands, Style {
+        position_type: PositionType::Absolute,
+        top: Val::Percent(50.0),
+        left: Val::Px(420.0),
+        ..default()
+    }, GameOver);
+}
+
+fn click_watch_replay_button(
+    mut playback: ResMut<ReplayPlayback>,
+    mut next_state: ResMut<NextState<GameState>>,
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<WatchReplayButton>)>,
+) {
+    for interaction in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            playback.requested = true;
+            next_state.set(GameState::Playing);
+        }
+    }
+}
+
+fn cleanup_replay_menu(mut commands: Commands, menu: Query<Entity, With<ReplayMenu>>) {
+    for entity in menu.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,85 @@
+use bevy::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+// Small persistence layer shared by everything that needs to survive a restart.
+// On native targets every save is a RON file in the platform data dir,
+// on wasm it lives in the browser localStorage under a `recycler.` prefixed key.
+
+#[cfg(not(target_arch = "wasm32"))]
+fn save_path(name: &str) -> Option<std::path::PathBuf> {
+    // The tests start from a clean slate and never overwrite the player's saves
+    if cfg!(test) {
+        return None;
+    }
+
+    directories::ProjectDirs::from("me", "engodev", "recycler")
+        .map(|dirs| dirs.data_dir().join(format!("{name}.ron")))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read(name: &str) -> Option<String> {
+    std::fs::read_to_string(save_path(name)?).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write(name: &str, contents: &str) -> Result<(), String> {
+    let path = save_path(name).ok_or("no data directory available")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|error| error.to_string())?;
+    }
+    std::fs::write(path, contents).map_err(|error| error.to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read(name: &str) -> Option<String> {
+    local_storage()?.get_item(&format!("recycler.{name}")).ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write(name: &str, contents: &str) -> Result<(), String> {
+    local_storage()
+        .ok_or("localStorage is not available")?
+        .set_item(&format!("recycler.{name}"), contents)
+        .map_err(|error| format!("{error:?}"))
+}
+
+/// Loads a previously saved value, returns `None` when nothing was saved yet or the save is unreadable
+pub fn load<T: DeserializeOwned>(name: &str) -> Option<T> {
+    let contents = read(name)?;
+    match ron::from_str(&contents) {
+        Ok(value) => Some(value),
+        Err(error) => {
+            warn!("Failed to parse save {name}: {error}");
+            None
+        }
+    }
+}
+
+pub fn save<T: Serialize>(name: &str, value: &T) {
+    save_with(name, ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default()));
+}
+
+/// Same as `save` without the pretty printing, for big saves nobody reads by hand
+pub fn save_compact<T: Serialize>(name: &str, value: &T) {
+    save_with(name, ron::ser::to_string(value));
+}
+
+fn save_with(name: &str, serialized: ron::Result<String>) {
+    let contents = match serialized {
+        Ok(contents) => contents,
+        Err(error) => {
+            warn!("Failed to serialize save {name}: {error}");
+            return;
+        }
+    };
+
+    if let Err(error) = write(name, &contents) {
+        warn!("Failed to write save {name}: {error}");
+    }
+}
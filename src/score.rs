@@ -2,9 +2,16 @@ use bevy::prelude::*;
 use bevy_progressbar::{ProgressBarMaterial, ProgressBar, ProgressBarBundle};
 
 use crate::GameState;
-use crate::game::update_on_wrong_letter;
-use crate::trash::{TrashMarked, handle_trash_collision};
-use crate::typing::TypingBuffer;
+use crate::game_mode::GameMode;
+use crate::events::{BossCleared, ComboLevelUp, CorrectKey, TrashDestroyed, TrashSorted, WordCompleted, WrongKey};
+use crate::typing::{PerPlayer, Player};
+
+// Added to the score (times the combo) for trash sorted into the right bin
+const SORTING_BONUS: usize = 5;
+// Times the number of trash the explosion destroyed so far, a bigger blast is worth more per trash
+const EXPLOSION_BONUS: usize = 2;
+// Times the number of words of the boss and the combo, on top of the words themselves
+const BOSS_BONUS: usize = 10;
 
 pub struct ScorePlugin;
 
@@ -12,26 +19,32 @@ pub struct ScorePlugin;
 #[derive(Component)]
 pub struct ScoreMarker;
 
+/// The progress bar toward the next combo level of a player
 #[derive(Component)]
-pub struct ComboMeter;
+pub struct ComboMeter(pub Player);
 
+/// The score of each player, solo runs only have the one of `Player::One`
 #[derive(Resource)]
-pub struct Score(pub usize);
+pub struct Score(pub PerPlayer<usize>);
 
 #[derive(Resource)]
-pub struct ComboModifier(pub usize);
+pub struct ComboModifier(pub PerPlayer<usize>);
 
 /// This plugin handles player related stuff like movement
 /// Player logic is only active during the State `GameState::Playing`
 impl Plugin for ScorePlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(Score(0))
-        .insert_resource(ComboModifier(1))
+        app.insert_resource(Score(PerPlayer::new(0)))
+        .insert_resource(ComboModifier(PerPlayer::new(1)))
         // .insert_resource(ProgressBarMaterial)
         .add_systems(OnEnter(GameState::Playing), setup)
         .add_systems(Update, (
+                score_completed_words.before(update_score),
+                update_modifier.before(score_completed_words),
+                score_sorted_trash.after(update_modifier).before(update_score),
+                score_destroyed_trash.after(update_modifier).before(update_score),
+                score_cleared_bosses.after(update_modifier).before(update_score),
                 update_score,
-                update_modifier.after(update_on_wrong_letter)
             ));
         //     .add_systems(Update, move_player.run_if(in_state(GameState::Playing)));
     }
@@ -42,12 +55,20 @@ fn setup(
     mut commands: Commands,
     mut score: ResMut<Score>,
     mut combo_modifier: ResMut<ComboModifier>,
-    materials: ResMut<Assets<ProgressBarMaterial>>,
+    mut materials: ResMut<Assets<ProgressBarMaterial>>,
+    game_mode: Res<GameMode>,
     // score: Res<Score>,
     // combo_modifier: Res<ComboModifier>,
 ) {
-    score.0 = 0;
-    combo_modifier.0 = 1;
+    score.0 = PerPlayer::new(0);
+    combo_modifier.0 = PerPlayer::new(1);
+
+    // Each versus side shows its own score and combo, with its combo bar under its playfield
+    if *game_mode == GameMode::Versus {
+        create_combo_progress_bar(&mut commands, &mut materials, Player::One, 0.0, 50.0);
+        create_combo_progress_bar(&mut commands, &mut materials, Player::Two, 50.0, 50.0);
+        return;
+    }
 
     commands.spawn(TextBundle {
         text: Text {
@@ -61,7 +82,7 @@ fn setup(
                     },
                 },
                 TextSection {
-                    value: format!("{}", score.0),
+                    value: format!("{}", score.0[Player::One]),
                     style: TextStyle {
                         font_size: 40.0,
                         color: Color::RED,
@@ -77,7 +98,7 @@ fn setup(
                     },
                 },
                 TextSection {
-                    value: format!("{}", combo_modifier.0),
+                    value: format!("{}", combo_modifier.0[Player::One]),
                     style: TextStyle {
                         font_size: 40.0,
                         color: Color::BLUE,
@@ -91,18 +112,23 @@ fn setup(
     })
     .insert(ScoreMarker);
 
-    create_combo_progress_bar(commands, materials);
+    create_combo_progress_bar(&mut commands, &mut materials, Player::One, 0.0, 100.0);
 }
 
+/// The combo bar of a player at the bottom of the window, `left` and `width` are in percent of the window
 fn create_combo_progress_bar(
-    mut commands: Commands,
-    mut materias: ResMut<Assets<ProgressBarMaterial>>
+    commands: &mut Commands,
+    materias: &mut Assets<ProgressBarMaterial>,
+    player: Player,
+    left: f32,
+    width: f32,
 ) {
     let bar = ProgressBar::single(Color::CYAN);
     let style = Style {
         position_type: PositionType::Absolute,
-        width: Val::Percent(100.0),
+        width: Val::Percent(width),
         height: Val::Px(10.0),
+        left: Val::Percent(left),
         bottom: Val::Px(0.0),
         // top: Val::Px(400.0),
         // right: Val::Percent(50.0),
@@ -110,8 +136,8 @@ fn create_combo_progress_bar(
     };
 
     commands.spawn(
-        ProgressBarBundle::new(style, bar, &mut materias)
-    ).insert(ComboMeter);
+        ProgressBarBundle::new(style, bar, materias)
+    ).insert(ComboMeter(player));
 }
 
 
@@ -125,27 +151,105 @@ fn update_score(
     }
 
     for mut text in &mut query.iter_mut() {
-        text.sections[1].value = format!("{}", score.0);
-        text.sections[3].value = format!("{}", combo_modifier.0);
+        text.sections[1].value = format!("{}", score.0[Player::One]);
+        text.sections[3].value = format!("{}", combo_modifier.0[Player::One]);
+    }
+}
+
+fn score_completed_words(
+    mut word_completed_events: EventReader<WordCompleted>,
+    mut score: ResMut<Score>,
+    combo_modifier: Res<ComboModifier>,
+) {
+    for word_completed in word_completed_events.read() {
+        let player = word_completed.player;
+        score.0[player] += word_completed.word.chars().count() * combo_modifier.0[player];
+    }
+}
+
+fn reset_combo_meter(combo_meter_query: &mut Query<(&mut ProgressBar, &ComboMeter)>, player: Player) {
+    for (mut progress_bar, combo_meter) in combo_meter_query.iter_mut() {
+        if combo_meter.0 == player {
+            progress_bar.reset();
+        }
+    }
+}
+
+/// The right bin earns a bonus, the wrong one costs a combo level
+fn score_sorted_trash(
+    mut trash_sorted_events: EventReader<TrashSorted>,
+    mut score: ResMut<Score>,
+    mut combo_modifier: ResMut<ComboModifier>,
+    mut combo_meter_query: Query<(&mut ProgressBar, &ComboMeter)>,
+) {
+    for trash_sorted in trash_sorted_events.read() {
+        let player = trash_sorted.player;
+        if trash_sorted.material == trash_sorted.bin {
+            score.0[player] += SORTING_BONUS * combo_modifier.0[player];
+            continue;
+        }
+
+        combo_modifier.0[player] = combo_modifier.0[player].saturating_sub(1).max(1);
+        reset_combo_meter(&mut combo_meter_query, player);
+    }
+}
+
+fn score_destroyed_trash(
+    mut trash_destroyed_events: EventReader<TrashDestroyed>,
+    mut score: ResMut<Score>,
+    combo_modifier: Res<ComboModifier>,
+) {
+    for trash_destroyed in trash_destroyed_events.read() {
+        // Explosions only go off in solo runs
+        score.0[Player::One] += EXPLOSION_BONUS * trash_destroyed.blast_count * combo_modifier.0[Player::One];
+    }
+}
+
+fn score_cleared_bosses(
+    mut boss_cleared_events: EventReader<BossCleared>,
+    mut score: ResMut<Score>,
+    combo_modifier: Res<ComboModifier>,
+) {
+    for boss_cleared in boss_cleared_events.read() {
+        // Bosses only fall in solo runs
+        score.0[Player::One] += BOSS_BONUS * boss_cleared.words * combo_modifier.0[Player::One];
     }
 }
 
 fn update_modifier(
-    typing_buffer: Res<TypingBuffer>,
+    mut correct_key_events: EventReader<CorrectKey>,
+    mut wrong_key_events: EventReader<WrongKey>,
+    mut combo_level_up_events: EventWriter<ComboLevelUp>,
     mut combo_modifier: ResMut<ComboModifier>,
-    mut combo_meter_query: Query<&mut ProgressBar, With<ComboMeter>>,
-    marked_trash_query: Query<Entity, With<TrashMarked>>,
+    mut combo_meter_query: Query<(&mut ProgressBar, &ComboMeter)>,
 ) {
-    if !typing_buffer.is_changed() {
-        return;
+    // An emptied buffer isn't a key the player missed, the screen can be empty between two spawns
+    let missed: Vec<Player> = wrong_key_events.read()
+        .filter(|wrong_key| wrong_key.letter.is_some())
+        .map(|wrong_key| wrong_key.player)
+        .collect();
+    for player in missed.iter() {
+        combo_modifier.0[*player] = 1;
+        reset_combo_meter(&mut combo_meter_query, *player);
     }
 
-    for mut progress_bar in &mut combo_meter_query.iter_mut() {
-        if progress_bar.is_finished() {
-            combo_modifier.0 += 1;
-            progress_bar.reset();
-        } else if !marked_trash_query.is_empty() {
-            progress_bar.increase_progress(0.1);
+    for correct_key in correct_key_events.read() {
+        if missed.contains(&correct_key.player) {
+            continue;
+        }
+
+        for (mut progress_bar, combo_meter) in combo_meter_query.iter_mut() {
+            if combo_meter.0 != correct_key.player {
+                continue;
+            }
+
+            if progress_bar.is_finished() {
+                combo_modifier.0[correct_key.player] += 1;
+                progress_bar.reset();
+                combo_level_up_events.send(ComboLevelUp { level: combo_modifier.0[correct_key.player] });
+            } else {
+                progress_bar.increase_progress(0.1);
+            }
         }
     }
 }
@@ -0,0 +1,227 @@
+use bevy::prelude::*;
+use chrono::{Datelike, NaiveDate, Utc};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+use crate::GameState;
+use crate::menu::{ButtonColors, TextInput, spawn_text_input};
+
+pub struct SeedPlugin;
+
+const MAX_SEED_LENGTH: usize = 18;
+const WORDS_SEED_SALT: u64 = 0x9E37_79B9_7F4A_7C15;
+const ENABLED_DAILY_COLOR: Color = Color::rgb(0.15, 0.45, 0.15);
+
+
+/// How the seed of the next run is picked, set from the menu
+#[derive(Resource, Default, Clone)]
+pub struct SeedSettings {
+    pub daily: bool,
+    pub custom_seed: Option<u64>,
+}
+
+/// The seed of the current run, the same seed always spawns the same sequence of trash
+#[derive(Resource, Default)]
+pub struct RunSeed {
+    pub seed: u64,
+    /// Daily runs share the seed and the word list of everyone playing on the same (UTC) day
+    pub daily: Option<NaiveDate>,
+}
+
+/// Every random choice of the run goes through these.
+/// The words have their own generator since the choice depends on the words already on screen,
+/// so the trash kinds, power ups and positions stay the same whatever the player types.
+#[derive(Resource)]
+pub struct RunRng {
+    pub trash: StdRng,
+    pub words: StdRng,
+}
+
+#[derive(Component)]
+struct SeedMenu;
+
+#[derive(Component)]
+struct DailyToggle;
+
+#[derive(Component)]
+struct SeedInput;
+
+
+impl Plugin for SeedPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SeedSettings>()
+        .init_resource::<RunSeed>()
+        .insert_resource(RunRng::from_seed(0))
+        .add_systems(OnEnter(GameState::Playing), seed_run)
+        .add_systems(OnEnter(GameState::Menu), spawn_seed_menu)
+        .add_systems(Update, (
+                toggle_daily.run_if(in_state(GameState::Menu)),
+                update_custom_seed.run_if(in_state(GameState::Menu)),
+            ))
+        .add_systems(OnExit(GameState::Menu), cleanup_seed_menu);
+    }
+}
+
+
+impl RunSeed {
+    pub fn is_daily(&self) -> bool {
+        self.daily.is_some()
+    }
+
+    pub fn label(&self) -> String {
+        match self.daily {
+            Some(date) => format!("Daily {} - Seed: {}", date.format("%Y-%m-%d"), self.seed),
+            None => format!("Seed: {}", self.seed),
+        }
+    }
+}
+
+impl RunRng {
+    pub fn from_seed(seed: u64) -> Self {
+        Self {
+            trash: StdRng::seed_from_u64(seed),
+            words: StdRng::seed_from_u64(seed ^ WORDS_SEED_SALT),
+        }
+    }
+}
+
+/// The seed of a daily run is the date written as a number, e.g. 20240131
+pub fn daily_seed(date: NaiveDate) -> u64 {
+    date.year() as u64 * 10_000 + date.month() as u64 * 100 + date.day() as u64
+}
+
+
+pub fn seed_run(
+    seed_settings: Res<SeedSettings>,
+    mut run_seed: ResMut<RunSeed>,
+    mut run_rng: ResMut<RunRng>,
+) {
+    *run_seed = if seed_settings.daily {
+        let today = Utc::now().date_naive();
+        RunSeed {
+            seed: daily_seed(today),
+            daily: Some(today),
+        }
+    } else {
+        RunSeed {
+            // Kept short so it's easy to share and type back in the menu
+            seed: seed_settings.custom_seed.unwrap_or_else(|| rand::thread_rng().gen::<u32>() as u64),
+            daily: None,
+        }
+    };
+
+    *run_rng = RunRng::from_seed(run_seed.seed);
+    info!("Starting run with {}", run_seed.label());
+}
+
+fn daily_label(daily: bool) -> String {
+    format!("Daily challenge: {}", if daily { "On" } else { "Off" })
+}
+
+fn daily_color(daily: bool) -> Color {
+    if daily {
+        ENABLED_DAILY_COLOR
+    } else {
+        ButtonColors::default().normal
+    }
+}
+
+fn spawn_seed_menu(mut commands: Commands, seed_settings: Res<SeedSettings>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    top: Val::Percent(15.0),
+                    flex_direction: FlexDirection::Row,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            SeedMenu,
+        ))
+        .with_children(|children| {
+            let button_colors = ButtonColors {
+                normal: daily_color(seed_settings.daily),
+                ..default()
+            };
+            children
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            height: Val::Px(36.0),
+                            padding: UiRect::horizontal(Val::Px(10.0)),
+                            margin: UiRect::new(Val::Px(4.0), Val::Px(4.0), Val::Px(5.0), Val::Px(0.0)),
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: button_colors.normal.into(),
+                        ..default()
+                    },
+                    button_colors,
+                    DailyToggle,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        daily_label(seed_settings.daily),
+                        TextStyle {
+                            font_size: 20.0,
+                            color: Color::rgb(0.9, 0.9, 0.9),
+                            ..default()
+                        },
+                    ));
+                });
+
+            spawn_text_input(children, Val::Px(200.0), TextInput {
+                value: seed_settings.custom_seed.map(|seed| seed.to_string()).unwrap_or_default(),
+                placeholder: "Seed (random)",
+                max_length: MAX_SEED_LENGTH,
+                accepts: |character| character.is_ascii_digit(),
+                focused: false,
+            })
+            .insert(SeedInput);
+        });
+}
+
+#[allow(clippy::type_complexity)]
+fn toggle_daily(
+    mut seed_settings: ResMut<SeedSettings>,
+    mut interaction_query: Query<
+        (&Interaction, &mut BackgroundColor, &mut ButtonColors, &Children),
+        (Changed<Interaction>, With<DailyToggle>),
+    >,
+    mut text_query: Query<&mut Text>,
+) {
+    for (interaction, mut color, mut button_colors, children) in &mut interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        seed_settings.daily = !seed_settings.daily;
+        button_colors.normal = daily_color(seed_settings.daily);
+        *color = button_colors.normal.into();
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.sections[0].value = daily_label(seed_settings.daily);
+            }
+        }
+    }
+}
+
+fn update_custom_seed(
+    mut seed_settings: ResMut<SeedSettings>,
+    seed_input_query: Query<&TextInput, (With<SeedInput>, Changed<TextInput>)>,
+) {
+    for text_input in seed_input_query.iter() {
+        seed_settings.custom_seed = text_input.value.parse().ok();
+    }
+}
+
+fn cleanup_seed_menu(mut commands: Commands, menu: Query<Entity, With<SeedMenu>>) {
+    for entity in menu.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
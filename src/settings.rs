@@ -0,0 +1,501 @@
+use bevy::prelude::*;
+use bevy::window::{PrimaryWindow, WindowMode};
+use serde::{Deserialize, Serialize};
+
+use crate::GameState;
+use crate::difficulty::DifficultyPreset;
+use crate::menu::{ButtonColors, ChangeState};
+use crate::save;
+use crate::words::WordStyle;
+
+pub struct SettingsPlugin;
+
+const SETTINGS_SAVE_NAME: &str = "settings";
+pub const MAX_STARTING_DIFFICULTY: u32 = 10;
+pub const MAX_WORD_LENGTH: usize = 18;
+
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum HighlightPalette {
+    #[default]
+    Standard,
+    Deuteranopia,
+    Tritanopia,
+}
+
+/// The physical keyboard of the player, typing follows the OS layout anyway
+/// but the screens that show keys (versus halves, miss heatmap) need to know where they are
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum KeyboardLayout {
+    #[default]
+    Qwerty,
+    Azerty,
+    Qwertz,
+    Dvorak,
+    Colemak,
+}
+
+/// Player preferences, saved whenever the settings screen is closed and loaded at startup
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Settings {
+    pub music_volume: f64,
+    pub sfx_volume: f64,
+    pub starting_difficulty: u32,
+    pub difficulty: DifficultyPreset,
+    /// The difficulty eases off when the accuracy drops
+    pub adaptive_difficulty: bool,
+    pub min_word_length: usize,
+    pub max_word_length: usize,
+    pub word_style: WordStyle,
+    pub highlight_palette: HighlightPalette,
+    pub keyboard_layout: KeyboardLayout,
+    /// Completed words wait for the key of a recycling bin
+    pub sorting_bins: bool,
+    pub fullscreen: bool,
+    pub on_screen_keyboard: bool,
+    /// Typing with the letter picker of a gamepad, the trash spawns slower
+    pub gamepad_typing: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum SettingKind {
+    MusicVolume,
+    SfxVolume,
+    StartingDifficulty,
+    Difficulty,
+    AdaptiveDifficulty,
+    MinWordLength,
+    MaxWordLength,
+    Capitals,
+    Punctuation,
+    Numbers,
+    HighlightPalette,
+    KeyboardLayout,
+    SortingBins,
+    Fullscreen,
+    OnScreenKeyboard,
+    GamepadTyping,
+}
+
+#[derive(Component)]
+struct SettingsMenu;
+
+/// Arrow buttons that change a setting by `delta` steps
+#[derive(Component)]
+struct SettingStep {
+    kind: SettingKind,
+    delta: i32,
+}
+
+#[derive(Component)]
+struct SettingValue(SettingKind);
+
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        let mut settings = save::load::<Settings>(SETTINGS_SAVE_NAME).unwrap_or_default();
+        settings.fix_word_lengths();
+
+        app.insert_resource(settings)
+        .add_systems(Startup, apply_window_mode)
+        .add_systems(OnEnter(GameState::Settings), spawn_settings_menu)
+        .add_systems(Update, (
+                click_settings_buttons.run_if(in_state(GameState::Settings)),
+                update_setting_values.after(click_settings_buttons).run_if(in_state(GameState::Settings)),
+                apply_window_mode.after(click_settings_buttons).run_if(in_state(GameState::Settings)),
+            ))
+        .add_systems(OnExit(GameState::Settings), (save_settings, cleanup_settings_menu));
+    }
+}
+
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            music_volume: 0.3,
+            sfx_volume: 0.5,
+            starting_difficulty: 0,
+            difficulty: DifficultyPreset::Normal,
+            adaptive_difficulty: false,
+            min_word_length: 1,
+            max_word_length: MAX_WORD_LENGTH,
+            word_style: WordStyle::default(),
+            highlight_palette: HighlightPalette::Standard,
+            keyboard_layout: KeyboardLayout::Qwerty,
+            sorting_bins: false,
+            fullscreen: false,
+            // Phones and tablets usually have no physical keyboard
+            on_screen_keyboard: cfg!(any(target_os = "android", target_os = "ios")),
+            gamepad_typing: false,
+        }
+    }
+}
+
+impl HighlightPalette {
+    /// The color of the letters that were already typed
+    pub fn highlight_color(&self) -> Color {
+        match self {
+            Self::Standard => Color::GREEN,
+            Self::Deuteranopia => Color::rgb(0.25, 0.6, 1.0),
+            Self::Tritanopia => Color::rgb(1.0, 0.4, 0.7),
+        }
+    }
+
+    fn next(&self, delta: i32) -> Self {
+        const PALETTES: [HighlightPalette; 3] = [
+            HighlightPalette::Standard,
+            HighlightPalette::Deuteranopia,
+            HighlightPalette::Tritanopia,
+        ];
+        let index = PALETTES.iter().position(|palette| palette == self).unwrap_or(0) as i32;
+        PALETTES[(index + delta).rem_euclid(PALETTES.len() as i32) as usize]
+    }
+}
+
+impl KeyboardLayout {
+    /// The three letter rows of the layout, ten keys each from left to right
+    pub fn rows(&self) -> [&'static str; 3] {
+        match self {
+            Self::Qwerty => ["qwertyuiop", "asdfghjkl;", "zxcvbnm,./"],
+            Self::Azerty => ["azertyuiop", "qsdfghjklm", "wxcvbn,;:!"],
+            Self::Qwertz => ["qwertzuiop", "asdfghjklö", "yxcvbnm,.-"],
+            Self::Dvorak => ["',.pyfgcrl", "aoeuidhtns", ";qjkxbmwvz"],
+            Self::Colemak => ["qwfpgjluy;", "arstdhneio", "zxcvbkm,./"],
+        }
+    }
+
+    /// The keys typed with the left hand, the first five of every row
+    pub fn left_half(&self) -> String {
+        self.rows().iter().flat_map(|row| row.chars().take(5)).collect()
+    }
+
+    pub fn right_half(&self) -> String {
+        self.rows().iter().flat_map(|row| row.chars().skip(5)).collect()
+    }
+
+    fn next(&self, delta: i32) -> Self {
+        const LAYOUTS: [KeyboardLayout; 5] = [
+            KeyboardLayout::Qwerty,
+            KeyboardLayout::Azerty,
+            KeyboardLayout::Qwertz,
+            KeyboardLayout::Dvorak,
+            KeyboardLayout::Colemak,
+        ];
+        let index = LAYOUTS.iter().position(|layout| layout == self).unwrap_or(0) as i32;
+        LAYOUTS[(index + delta).rem_euclid(LAYOUTS.len() as i32) as usize]
+    }
+}
+
+impl SettingKind {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::MusicVolume => "Music volume",
+            Self::SfxVolume => "Effects volume",
+            Self::StartingDifficulty => "Starting difficulty",
+            Self::Difficulty => "Difficulty",
+            Self::AdaptiveDifficulty => "Adaptive difficulty",
+            Self::MinWordLength => "Shortest word",
+            Self::MaxWordLength => "Longest word",
+            Self::Capitals => "Capitals",
+            Self::Punctuation => "Punctuation",
+            Self::Numbers => "Numbers",
+            Self::HighlightPalette => "Highlight colors",
+            Self::KeyboardLayout => "Keyboard layout",
+            Self::SortingBins => "Sorting bins",
+            Self::Fullscreen => "Fullscreen",
+            Self::OnScreenKeyboard => "On-screen keyboard",
+            Self::GamepadTyping => "Gamepad typing",
+        }
+    }
+}
+
+impl Settings {
+    /// Settings and replay files can be edited by hand, this brings the word lengths back to what the steppers allow
+    /// (a shortest word longer than the longest one would leave no length to pick from)
+    pub fn fix_word_lengths(&mut self) {
+        self.min_word_length = self.min_word_length.clamp(1, MAX_WORD_LENGTH);
+        self.max_word_length = self.max_word_length.clamp(self.min_word_length, MAX_WORD_LENGTH);
+    }
+
+    fn step(&mut self, kind: SettingKind, delta: i32) {
+        match kind {
+            SettingKind::MusicVolume => {
+                self.music_volume = (self.music_volume + 0.1 * delta as f64).clamp(0.0, 1.0);
+            },
+            SettingKind::SfxVolume => {
+                self.sfx_volume = (self.sfx_volume + 0.1 * delta as f64).clamp(0.0, 1.0);
+            },
+            SettingKind::StartingDifficulty => {
+                self.starting_difficulty = self.starting_difficulty
+                    .saturating_add_signed(delta)
+                    .min(MAX_STARTING_DIFFICULTY);
+            },
+            SettingKind::Difficulty => {
+                self.difficulty = self.difficulty.next(delta);
+            },
+            SettingKind::AdaptiveDifficulty => {
+                self.adaptive_difficulty = !self.adaptive_difficulty;
+            },
+            SettingKind::MinWordLength => {
+                self.min_word_length = self.min_word_length
+                    .saturating_add_signed(delta as isize)
+                    .clamp(1, self.max_word_length);
+            },
+            SettingKind::MaxWordLength => {
+                self.max_word_length = self.max_word_length
+                    .saturating_add_signed(delta as isize)
+                    .clamp(self.min_word_length, MAX_WORD_LENGTH);
+            },
+            SettingKind::Capitals => {
+                self.word_style.capitals = !self.word_style.capitals;
+            },
+            SettingKind::Punctuation => {
+                self.word_style.punctuation = !self.word_style.punctuation;
+            },
+            SettingKind::Numbers => {
+                self.word_style.numbers = !self.word_style.numbers;
+            },
+            SettingKind::HighlightPalette => {
+                self.highlight_palette = self.highlight_palette.next(delta);
+            },
+            SettingKind::KeyboardLayout => {
+                self.keyboard_layout = self.keyboard_layout.next(delta);
+            },
+            SettingKind::SortingBins => {
+                self.sorting_bins = !self.sorting_bins;
+            },
+            SettingKind::Fullscreen => {
+                self.fullscreen = !self.fullscreen;
+            },
+            SettingKind::OnScreenKeyboard => {
+                self.on_screen_keyboard = !self.on_screen_keyboard;
+            },
+            SettingKind::GamepadTyping => {
+                self.gamepad_typing = !self.gamepad_typing;
+            },
+        }
+    }
+
+    fn display(&self, kind: SettingKind) -> String {
+        match kind {
+            SettingKind::MusicVolume => format!("{:.0}%", self.music_volume * 100.0),
+            SettingKind::SfxVolume => format!("{:.0}%", self.sfx_volume * 100.0),
+            SettingKind::StartingDifficulty => self.starting_difficulty.to_string(),
+            SettingKind::Difficulty => format!("{:?}", self.difficulty),
+            SettingKind::AdaptiveDifficulty => on_off(self.adaptive_difficulty),
+            SettingKind::MinWordLength => self.min_word_length.to_string(),
+            SettingKind::MaxWordLength => self.max_word_length.to_string(),
+            SettingKind::Capitals => on_off(self.word_style.capitals),
+            SettingKind::Punctuation => on_off(self.word_style.punctuation),
+            SettingKind::Numbers => on_off(self.word_style.numbers),
+            SettingKind::HighlightPalette => format!("{:?}", self.highlight_palette),
+            SettingKind::KeyboardLayout => format!("{:?}", self.keyboard_layout),
+            SettingKind::SortingBins => on_off(self.sorting_bins),
+            SettingKind::Fullscreen => on_off(self.fullscreen),
+            SettingKind::OnScreenKeyboard => on_off(self.on_screen_keyboard),
+            SettingKind::GamepadTyping => on_off(self.gamepad_typing),
+        }
+    }
+}
+
+
+fn on_off(enabled: bool) -> String {
+    if enabled { "On" } else { "Off" }.to_string()
+}
+
+fn apply_window_mode(
+    settings: Res<Settings>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    let mode = if settings.fullscreen {
+        WindowMode::BorderlessFullscreen
+    } else {
+        WindowMode::Windowed
+    };
+
+    for mut window in window_query.iter_mut() {
+        if window.mode != mode {
+            window.mode = mode;
+        }
+    }
+}
+
+fn save_settings(settings: Res<Settings>) {
+    save::save(SETTINGS_SAVE_NAME, &*settings);
+}
+
+fn spawn_text(parent: &mut ChildBuilder, value: String, width: f32, bundle: impl Bundle) {
+    parent.spawn((
+        TextBundle {
+            text: Text::from_section(
+                value,
+                TextStyle {
+                    font_size: 25.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                    ..default()
+                },
+            )
+            .with_alignment(TextAlignment::Center),
+            style: Style {
+                width: Val::Px(width),
+                ..default()
+            },
+            ..default()
+        },
+        bundle,
+    ));
+}
+
+fn spawn_button(parent: &mut ChildBuilder, label: &str, width: f32, bundle: impl Bundle) {
+    let button_colors = ButtonColors::default();
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(width),
+                    height: Val::Px(34.0),
+                    margin: UiRect::all(Val::Px(3.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: button_colors.normal.into(),
+                ..default()
+            },
+            button_colors,
+            bundle,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                label,
+                TextStyle {
+                    font_size: 25.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn spawn_settings_menu(mut commands: Commands, settings: Res<Settings>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            SettingsMenu,
+        ))
+        .with_children(|children| {
+            children.spawn(TextBundle::from_section(
+                "Settings",
+                TextStyle {
+                    font_size: 60.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+
+            for kind in [
+                SettingKind::MusicVolume,
+                SettingKind::SfxVolume,
+                SettingKind::StartingDifficulty,
+                SettingKind::Difficulty,
+                SettingKind::AdaptiveDifficulty,
+                SettingKind::MinWordLength,
+                SettingKind::MaxWordLength,
+                SettingKind::Capitals,
+                SettingKind::Punctuation,
+                SettingKind::Numbers,
+                SettingKind::HighlightPalette,
+                SettingKind::KeyboardLayout,
+                SettingKind::SortingBins,
+                SettingKind::Fullscreen,
+                SettingKind::OnScreenKeyboard,
+                SettingKind::GamepadTyping,
+            ] {
+                children
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Row,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        spawn_text(row, kind.label().to_string(), 240.0, ());
+                        spawn_button(row, "<", 40.0, SettingStep { kind, delta: -1 });
+                        spawn_text(row, settings.display(kind), 160.0, SettingValue(kind));
+                        spawn_button(row, ">", 40.0, SettingStep { kind, delta: 1 });
+                    });
+            }
+
+            spawn_button(children, "Back", 140.0, ChangeState(GameState::Menu));
+        });
+}
+
+#[allow(clippy::type_complexity)]
+fn click_settings_buttons(
+    mut settings: ResMut<Settings>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut interaction_query: Query<
+        (
+            &Interaction,
+            &mut BackgroundColor,
+            &ButtonColors,
+            Option<&SettingStep>,
+            Option<&ChangeState>,
+        ),
+        (Changed<Interaction>, With<Button>),
+    >,
+) {
+    for (interaction, mut color, button_colors, setting_step, change_state) in &mut interaction_query {
+        match *interaction {
+            Interaction::Pressed => {
+                if let Some(setting_step) = setting_step {
+                    settings.step(setting_step.kind, setting_step.delta);
+                } else if let Some(state) = change_state {
+                    next_state.set(state.0.clone());
+                }
+            }
+            Interaction::Hovered => {
+                *color = button_colors.hovered.into();
+            }
+            Interaction::None => {
+                *color = button_colors.normal.into();
+            }
+        }
+    }
+}
+
+fn update_setting_values(
+    settings: Res<Settings>,
+    mut value_query: Query<(&SettingValue, &mut Text)>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    for (setting_value, mut text) in value_query.iter_mut() {
+        text.sections[0].value = settings.display(setting_value.0);
+    }
+}
+
+fn cleanup_settings_menu(mut commands: Commands, menu: Query<Entity, With<SettingsMenu>>) {
+    for entity in menu.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
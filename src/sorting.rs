@@ -0,0 +1,171 @@
+use bevy::prelude::*;
+
+use crate::GameState;
+use crate::catalogue::Material;
+use crate::events::TrashSorted;
+use crate::on_screen_keyboard::{OnScreenKeyboard, spawn_on_screen_keyboard};
+use crate::replay::start_replay;
+use crate::seed::RunSeed;
+use crate::settings::Settings;
+
+pub struct SortingPlugin;
+
+/// Typed after a word to send the trash into the bin of the same position in `Material::ALL`
+pub const BIN_KEYS: [char; 5] = ['1', '2', '3', '4', '5'];
+const BIN_FLASH_SECONDS: f32 = 0.4;
+
+
+#[derive(Component)]
+struct SortingBins;
+
+#[derive(Component)]
+struct Bin {
+    material: Material,
+    /// Counts down after something was sorted into the bin, it's drawn green or red meanwhile
+    flash: Timer,
+    correct: bool,
+}
+
+
+/// With sorting on, a completed word waits for a bin key and the trash goes into that bin
+impl Plugin for SortingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Playing), spawn_bins.after(start_replay).after(spawn_on_screen_keyboard))
+        .add_systems(OnExit(GameState::Playing), cleanup_bins)
+        .add_systems(Update, flash_bins.run_if(in_state(GameState::Playing)));
+    }
+}
+
+
+// Daily runs are played without the bins
+pub fn sorting_bins(settings: &Settings, run_seed: &RunSeed) -> bool {
+    !run_seed.is_daily() && settings.sorting_bins
+}
+
+/// The bin the buffer sends the word into, when the buffer is the word followed by a bin key
+pub fn sorted_into(word: &str, buffer: &str) -> Option<Material> {
+    let mut suffix = buffer.strip_prefix(word)?.chars();
+    match (suffix.next(), suffix.next()) {
+        (Some(key), None) => BIN_KEYS.iter().position(|bin_key| *bin_key == key).map(|bin| Material::ALL[bin]),
+        _ => None,
+    }
+}
+
+/// Adds the bin keys to the characters the on-screen keyboards offer
+pub fn with_bin_keys(mut characters: Vec<char>, settings: &Settings, run_seed: &RunSeed) -> Vec<char> {
+    if sorting_bins(settings, run_seed) {
+        characters.extend(BIN_KEYS);
+        characters.sort();
+        characters.dedup();
+    }
+
+    characters
+}
+
+fn spawn_bins(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    run_seed: Res<RunSeed>,
+    keyboard_query: Query<Entity, With<OnScreenKeyboard>>,
+) {
+    if !sorting_bins(&settings, &run_seed) {
+        return;
+    }
+
+    // With the on-screen keyboard the bins are its top row, they'd be hidden under it otherwise
+    let keyboard = keyboard_query.get_single().ok();
+    let style = match keyboard {
+        Some(_) => Style {
+            margin: UiRect::new(Val::Px(60.0), Val::Px(60.0), Val::Px(0.0), Val::Px(4.0)),
+            height: Val::Px(36.0),
+            flex_direction: FlexDirection::Row,
+            ..default()
+        },
+        None => Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(12.0),
+            left: Val::Px(60.0),
+            right: Val::Px(60.0),
+            height: Val::Px(36.0),
+            flex_direction: FlexDirection::Row,
+            ..default()
+        },
+    };
+
+    let bins = commands
+        .spawn((
+            NodeBundle {
+                style,
+                ..default()
+            },
+            SortingBins,
+        ))
+        .with_children(|parent| {
+            for (key, material) in BIN_KEYS.iter().zip(Material::ALL) {
+                parent
+                    .spawn((
+                        NodeBundle {
+                            style: Style {
+                                flex_grow: 1.0,
+                                margin: UiRect::horizontal(Val::Px(3.0)),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            background_color: material.color().with_a(0.8).into(),
+                            ..default()
+                        },
+                        Bin {
+                            material,
+                            flash: Timer::from_seconds(0.0, TimerMode::Once),
+                            correct: false,
+                        },
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            format!("{key} {material:?}"),
+                            TextStyle {
+                                font_size: 18.0,
+                                color: Color::WHITE,
+                                ..default()
+                            },
+                        ));
+                    });
+            }
+        })
+        .id();
+
+    if let Some(keyboard) = keyboard {
+        commands.entity(keyboard).insert_children(0, &[bins]);
+    }
+}
+
+fn flash_bins(
+    mut bin_query: Query<(&mut Bin, &mut BackgroundColor)>,
+    mut trash_sorted_events: EventReader<TrashSorted>,
+    time: Res<Time>,
+) {
+    let sorted: Vec<&TrashSorted> = trash_sorted_events.read().collect();
+
+    for (mut bin, mut background_color) in bin_query.iter_mut() {
+        if let Some(trash_sorted) = sorted.iter().find(|trash_sorted| trash_sorted.bin == bin.material) {
+            bin.correct = trash_sorted.material == trash_sorted.bin;
+            bin.flash = Timer::from_seconds(BIN_FLASH_SECONDS, TimerMode::Once);
+        }
+
+        let color = if bin.flash.tick(time.delta()).finished() {
+            bin.material.color()
+        } else if bin.correct {
+            Color::GREEN
+        } else {
+            Color::RED
+        };
+        *background_color = color.with_a(0.8).into();
+    }
+}
+
+fn cleanup_bins(mut commands: Commands, bins: Query<Entity, With<SortingBins>>) {
+    for entity in bins.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,193 @@
+use bevy::prelude::*;
+
+use crate::{GameState, PauseState};
+use crate::game_mode::is_solo;
+use crate::game_over::GameOverLine;
+use crate::menu::ButtonColors;
+use crate::settings::Settings;
+use crate::trash::{Trash, TrashActionActive};
+use crate::trash_text::TrashText;
+use crate::typing::{KeyEdit, Player, TypingBuffer, TypingKey, typing};
+
+pub struct TargetLockPlugin;
+
+// Leaves some room around the trash for a finger
+const TAP_MARGIN: f32 = 12.0;
+
+/// When enabled the first typed letter locks onto a single trash (zType style)
+/// and the rest of the word only advances that trash.
+/// Tapping a trash locks it even when disabled. Versus runs don't lock.
+#[derive(Resource, Default)]
+pub struct TargetLock {
+    pub enabled: bool,
+    pub target: Option<Entity>,
+    /// The target was tapped, it stays locked with an empty buffer until it's cleared
+    pub tapped: bool,
+}
+
+#[derive(Component)]
+pub struct TargetLockToggle;
+
+
+impl Plugin for TargetLockPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TargetLock>()
+        .add_systems(OnExit(GameState::Playing), release_target)
+        .add_systems(Update, (
+                lock_target.after(typing).run_if(in_state(GameState::Playing)).run_if(in_state(PauseState::Running)).run_if(is_solo),
+                draw_target_reticle.run_if(in_state(GameState::Playing)),
+                toggle_target_lock.run_if(in_state(GameState::Menu)),
+            ));
+    }
+}
+
+
+impl TargetLock {
+    /// Whether the typing buffer is allowed to act on the trash
+    pub fn allows(&self, trash: Entity) -> bool {
+        self.target.map_or(true, |target| target == trash)
+    }
+
+    pub fn label(&self) -> String {
+        format!("Target lock: {}", if self.enabled { "On" } else { "Off" })
+    }
+}
+
+
+/// The falling trash with a word under `position`, the closest one when they overlap
+pub fn trash_at(
+    position: Vec2,
+    trash_text_query: &Query<(&Parent, &TrashText)>,
+    trash_query: &Query<(&Transform, &Trash), With<TrashActionActive>>,
+) -> Option<Entity> {
+    trash_text_query.iter()
+        .filter_map(|(parent, _)| {
+            let (transform, trash) = trash_query.get(parent.get()).ok()?;
+            let distance = transform.translation.truncate().distance(position);
+            (distance <= trash.kind.size().max_element() + TAP_MARGIN).then_some((parent.get(), distance))
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(entity, _)| entity)
+}
+
+fn release_target(mut target_lock: ResMut<TargetLock>) {
+    target_lock.target = None;
+    target_lock.tapped = false;
+}
+
+pub fn lock_target(
+    mut target_lock: ResMut<TargetLock>,
+    mut typing_buffer: ResMut<TypingBuffer>,
+    mut typing_keys: EventReader<TypingKey>,
+    trash_text_query: Query<(&Parent, &TrashText)>,
+    trash_query: Query<(&Transform, &Trash), With<TrashActionActive>>,
+    game_over_line_query: Query<&Transform, With<GameOverLine>>,
+) {
+    for typing_key in typing_keys.read() {
+        match typing_key.edit {
+            KeyEdit::Target { x, y } => {
+                target_lock.target = trash_at(Vec2::new(x, y), &trash_text_query, &trash_query);
+                target_lock.tapped = target_lock.target.is_some();
+            },
+            KeyEdit::Clear => target_lock.tapped = false,
+            _ => {},
+        }
+    }
+
+    if let Some(target) = target_lock.target {
+        let is_target_alive = trash_text_query.iter().any(|(parent, _)| parent.get() == target)
+            && trash_query.get(target).is_ok();
+        if !is_target_alive {
+            target_lock.target = None;
+            target_lock.tapped = false;
+            typing_buffer.0[Player::One] = "".to_string();
+            return;
+        }
+    }
+
+    if typing_buffer.0[Player::One].is_empty() && !target_lock.tapped {
+        target_lock.target = None;
+        return;
+    }
+
+    if !target_lock.enabled || target_lock.target.is_some() {
+        return;
+    }
+
+    let line_y = game_over_line_query.get_single().map_or(0.0, |transform| transform.translation.y);
+    target_lock.target = trash_text_query.iter()
+        .filter(|(_, trash_text)| trash_text.word.starts_with(&typing_buffer.0[Player::One]))
+        .filter_map(|(parent, _)| {
+            let (transform, _) = trash_query.get(parent.get()).ok()?;
+            Some((parent.get(), (transform.translation.y - line_y).abs()))
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(entity, _)| entity);
+}
+
+fn draw_target_reticle(
+    mut gizmos: Gizmos,
+    target_lock: Res<TargetLock>,
+    settings: Res<Settings>,
+    trash_query: Query<(&Transform, &Trash)>,
+) {
+    let Some(target) = target_lock.target else {
+        return;
+    };
+
+    if let Ok((transform, trash)) = trash_query.get(target) {
+        let radius = trash.kind.size().max_element() + 12.0;
+        gizmos.circle_2d(transform.translation.truncate(), radius, settings.highlight_palette.highlight_color());
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn toggle_target_lock(
+    mut target_lock: ResMut<TargetLock>,
+    interaction_query: Query<(&Interaction, &Children), (Changed<Interaction>, With<TargetLockToggle>)>,
+    mut text_query: Query<&mut Text>,
+) {
+    for (interaction, children) in interaction_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        target_lock.enabled = !target_lock.enabled;
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.sections[0].value = target_lock.label();
+            }
+        }
+    }
+}
+
+pub fn spawn_target_lock_toggle(parent: &mut ChildBuilder, target_lock: &TargetLock) {
+    let button_colors = ButtonColors::default();
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(220.0),
+                    height: Val::Px(36.0),
+                    margin: UiRect::top(Val::Px(10.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: button_colors.normal.into(),
+                ..default()
+            },
+            button_colors,
+            TargetLockToggle,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                target_lock.label(),
+                TextStyle {
+                    font_size: 20.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                    ..default()
+                },
+            ));
+        });
+}
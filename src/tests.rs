@@ -0,0 +1,618 @@
+use std::f32::consts::TAU;
+
+use bevy::input::ButtonState;
+use bevy::input::gamepad::{GamepadAxisChangedEvent, GamepadButtonChangedEvent, GamepadConnection, GamepadConnectionEvent, GamepadEvent, GamepadInfo};
+use bevy::input::keyboard::KeyboardInput;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use bevy_rapier2d::prelude::{GravityScale, Velocity};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+use crate::GameState;
+use crate::catalogue::{Material, TrashCatalogue};
+use crate::game::DifficultyLevel;
+use crate::game_mode::GameMode;
+use crate::game_over::GameOverLine;
+use crate::headless::{current_state, headless_app, run_frames, run_until, type_letters, words_on_screen};
+use crate::loading::DataAssets;
+use crate::score::{ComboModifier, Score};
+use crate::settings::{KeyboardLayout, Settings};
+use crate::sorting::BIN_KEYS;
+use crate::target_lock::TargetLock;
+use crate::power_ups::{ActivePowerUps, PowerUp};
+use crate::trash::{Boss, Trash, TrashActionActive, TrashBundle, TrashMarked};
+use crate::typing::{KeyEdit, PerPlayer, Player, TypingBuffer, TypingKey};
+use crate::typing_stats::{TypingStats, heatmap_rows};
+use crate::versus::{VersusLoser, VersusPlayers};
+use crate::words::{AvailableWords, WordSources};
+
+const SEED: u64 = 1234;
+// A new trash falls every two seconds, this leaves room for a few of them
+const MAX_WAIT_FRAMES: u32 = 600;
+
+
+/// Waits for a trash without power up and returns it with its word
+fn wait_for_plain_trash(app: &mut App) -> (Entity, String) {
+    wait_for_plain_trash_among(app, 1)
+}
+
+/// Same as `wait_for_plain_trash`, once there are at least `min_words` words on screen
+fn wait_for_plain_trash_among(app: &mut App, min_words: usize) -> (Entity, String) {
+    let mut found = None;
+    let spawned = run_until(app, MAX_WAIT_FRAMES, |world| {
+        let words = words_on_screen(world);
+        found = words.iter()
+            .find(|(_, trash, _)| trash.power_up == PowerUp::None)
+            .filter(|_| words.len() >= min_words)
+            .map(|(entity, _, word)| (*entity, word.clone()));
+        found.is_some()
+    });
+    assert!(spawned, "no trash without power up spawned");
+
+    found.unwrap()
+}
+
+/// A letter none of the words on screen goes on with after what's already typed
+fn unused_letter(app: &mut App) -> char {
+    let typed = typing_buffer(app);
+    let words = words_on_screen(&mut app.world);
+    ('a'..='z')
+        .find(|letter| words.iter().all(|(_, _, word)| !word.starts_with(&format!("{typed}{letter}"))))
+        .expect("there are less words than letters")
+}
+
+/// The shortest start of the word that none of the other words on screen starts with
+fn unique_prefix(app: &mut App, word: &str) -> String {
+    let words = words_on_screen(&mut app.world);
+    (1..=word.chars().count())
+        .map(|length| word.chars().take(length).collect::<String>())
+        .find(|prefix| words.iter().filter(|(_, _, other)| other.starts_with(prefix.as_str())).count() == 1)
+        .unwrap_or_else(|| word.to_string())
+}
+
+/// Types through the text input of the window, the way the keys of the versus sides are read
+fn type_characters(app: &mut App, characters: &str) {
+    let window = app.world.query_filtered::<Entity, With<Window>>().single(&app.world);
+    for char in characters.chars() {
+        app.world.send_event(ReceivedCharacter { window, char });
+        app.update();
+    }
+}
+
+/// Presses and releases a key through the keyboard events of the window
+fn press_key(app: &mut App, key_code: KeyCode) {
+    let window = app.world.query_filtered::<Entity, With<Window>>().single(&app.world);
+    for state in [ButtonState::Pressed, ButtonState::Released] {
+        app.world.send_event(KeyboardInput { scan_code: 0, key_code: Some(key_code), state, window });
+        app.update();
+    }
+}
+
+/// Waits for a trash with a word on the side of the given player of a versus run
+fn wait_for_versus_word(app: &mut App, player: Player) -> (Entity, String) {
+    let mut found = None;
+    let spawned = run_until(app, MAX_WAIT_FRAMES, |world| {
+        found = words_on_screen(world).into_iter()
+            .find(|(entity, _, _)| world.get::<Player>(*entity) == Some(&player))
+            .map(|(entity, _, word)| (entity, word));
+        found.is_some()
+    });
+    assert!(spawned, "no trash fell on the side of {player:?}");
+
+    found.unwrap()
+}
+
+fn typing_buffer(app: &App) -> String {
+    player_buffer(app, Player::One)
+}
+
+fn player_buffer(app: &App, player: Player) -> String {
+    app.world.resource::<TypingBuffer>().0[player].clone()
+}
+
+
+#[test]
+fn completing_a_word_scores_it_and_clears_the_trash() {
+    let mut app = headless_app(SEED);
+    let (entity, word) = wait_for_plain_trash(&mut app);
+
+    type_letters(&mut app, &word);
+    run_frames(&mut app, 2);
+
+    assert_eq!(app.world.resource::<Score>().0[Player::One], word.len());
+    assert!(app.world.get_entity(entity).is_none());
+    assert_eq!(typing_buffer(&app), "");
+}
+
+#[test]
+fn words_outside_of_a_to_z_are_typed_and_scored_by_character() {
+    let mut app = headless_app(SEED);
+    app.world.resource_mut::<WordSources>().enabled_packs = HashSet::from(["deutsch".to_string()]);
+    let mut found = None;
+    let spawned = run_until(&mut app, MAX_WAIT_FRAMES * 2, |world| {
+        found = words_on_screen(world).into_iter()
+            .find(|(_, trash, word)| trash.power_up == PowerUp::None && !word.is_ascii())
+            .map(|(entity, _, word)| (entity, word));
+        found.is_some()
+    });
+    assert!(spawned, "no word with non-ASCII letters spawned");
+    let (entity, word) = found.unwrap();
+
+    type_letters(&mut app, &word);
+    run_frames(&mut app, 2);
+
+    assert_eq!(app.world.resource::<Score>().0[Player::One], word.chars().count());
+    assert!(app.world.get_entity(entity).is_none());
+}
+
+#[test]
+fn a_completed_word_waits_for_its_bin_and_earns_a_bonus() {
+    let mut app = headless_app(SEED);
+    app.world.resource_mut::<Settings>().sorting_bins = true;
+    let (entity, word) = wait_for_plain_trash(&mut app);
+    let material = app.world.get::<Trash>(entity).unwrap().kind.material;
+    let bin = Material::ALL.iter().position(|bin| *bin == material).unwrap();
+
+    type_letters(&mut app, &word);
+    run_frames(&mut app, 1);
+    assert!(app.world.get_entity(entity).is_some());
+    assert_eq!(app.world.resource::<Score>().0[Player::One], 0);
+
+    type_letters(&mut app, &BIN_KEYS[bin].to_string());
+    run_frames(&mut app, 2);
+
+    assert!(app.world.get_entity(entity).is_none());
+    assert!(app.world.resource::<Score>().0[Player::One] > word.len());
+    assert_eq!(typing_buffer(&app), "");
+}
+
+#[test]
+fn typing_a_prefix_marks_only_the_matching_trash() {
+    let mut app = headless_app(SEED);
+    let (entity, word) = wait_for_plain_trash(&mut app);
+    let prefix = unique_prefix(&mut app, &word);
+
+    type_letters(&mut app, &prefix);
+    run_frames(&mut app, 1);
+
+    let marked: Vec<Entity> = app.world.query_filtered::<Entity, With<TrashMarked>>().iter(&app.world).collect();
+    assert_eq!(marked, vec![entity]);
+    assert_eq!(typing_buffer(&app), prefix);
+}
+
+#[test]
+fn a_wrong_letter_is_dropped_and_resets_the_combo() {
+    let mut app = headless_app(SEED);
+    let (_, word) = wait_for_plain_trash(&mut app);
+
+    type_letters(&mut app, &word[..1]);
+    app.world.resource_mut::<ComboModifier>().0[Player::One] = 3;
+    let letter = unused_letter(&mut app);
+    type_letters(&mut app, &letter.to_string());
+    run_frames(&mut app, 1);
+
+    assert_eq!(typing_buffer(&app), word[..1]);
+    assert_eq!(app.world.resource::<ComboModifier>().0[Player::One], 1);
+}
+
+#[test]
+fn a_wrong_letter_ends_a_sudden_death_run() {
+    let mut app = headless_app(SEED);
+    app.insert_resource(GameMode::SuddenDeath);
+    let (_, word) = wait_for_plain_trash(&mut app);
+
+    type_letters(&mut app, &word[..1]);
+    assert_eq!(current_state(&app), GameState::Playing);
+    let letter = unused_letter(&mut app);
+    type_letters(&mut app, &letter.to_string());
+
+    assert!(run_until(&mut app, 10, |world| *world.resource::<State<GameState>>().get() == GameState::GameOver));
+}
+
+#[test]
+fn a_phrase_is_typed_with_its_spaces() {
+    let mut app = headless_app(SEED);
+    app.insert_resource(GameMode::Phrases);
+    let (entity, phrase) = wait_for_plain_trash(&mut app);
+    assert!(phrase.contains(' '));
+
+    type_letters(&mut app, &phrase);
+    run_frames(&mut app, 2);
+
+    assert!(app.world.get_entity(entity).is_none());
+    assert_eq!(typing_buffer(&app), "");
+}
+
+#[test]
+fn missing_keys_lowers_the_adaptive_difficulty() {
+    let mut app = headless_app(SEED);
+    {
+        let mut settings = app.world.resource_mut::<Settings>();
+        settings.starting_difficulty = 3;
+        settings.adaptive_difficulty = true;
+    }
+    wait_for_plain_trash(&mut app);
+
+    let letter = unused_letter(&mut app);
+    type_letters(&mut app, &letter.to_string().repeat(3));
+
+    assert!(run_until(&mut app, MAX_WAIT_FRAMES * 2, |world| world.resource::<DifficultyLevel>().0 != 3));
+    assert_eq!(app.world.resource::<DifficultyLevel>().0, 2);
+}
+
+#[test]
+fn typing_stats_count_the_keys_and_the_misses_per_key() {
+    let mut app = headless_app(SEED);
+    let (_, word) = wait_for_plain_trash(&mut app);
+
+    type_letters(&mut app, &word[..1]);
+    let letter = unused_letter(&mut app);
+    type_letters(&mut app, &letter.to_string());
+    type_letters(&mut app, &letter.to_string());
+    run_frames(&mut app, 1);
+
+    let typing_stats = app.world.resource::<TypingStats>();
+    assert_eq!(typing_stats.correct_keys, 1);
+    assert_eq!(typing_stats.wrong_keys, 2);
+    assert_eq!(typing_stats.streak, 0);
+    assert_eq!(typing_stats.longest_streak, 1);
+    assert_eq!(typing_stats.misses.get(&letter), Some(&2));
+    assert_eq!(typing_stats.summary(60.0).accuracy, 1.0 / 3.0);
+}
+
+#[test]
+fn tapping_a_trash_locks_it_as_the_target() {
+    let mut app = headless_app(SEED);
+    let (entity, _) = wait_for_plain_trash_among(&mut app, 2);
+    let (_, _, other_word) = words_on_screen(&mut app.world).into_iter()
+        .find(|(other, _, _)| *other != entity)
+        .unwrap();
+
+    let position = app.world.get::<Transform>(entity).unwrap().translation;
+    app.world.send_event(TypingKey::from(KeyEdit::Target { x: position.x, y: position.y }));
+    run_frames(&mut app, 1);
+    assert_eq!(app.world.resource::<TargetLock>().target, Some(entity));
+
+    // The other words don't match while the tapped trash is locked
+    type_letters(&mut app, &other_word.chars().next().unwrap().to_string());
+    run_frames(&mut app, 1);
+
+    assert_eq!(typing_buffer(&app), "");
+    assert_eq!(app.world.resource::<TargetLock>().target, Some(entity));
+}
+
+#[test]
+fn the_gamepad_picker_types_the_character_of_the_sector_and_the_face_button() {
+    let mut app = headless_app(SEED);
+    app.world.resource_mut::<Settings>().gamepad_typing = true;
+    let (_, word) = wait_for_plain_trash(&mut app);
+    let letter = word.chars().next().unwrap();
+
+    // The left stick ring has four letters per sector from a to z, starting at the top and going clockwise
+    let index = letter as usize - 'a' as usize;
+    let angle = (index / 4) as f32 / 8.0 * TAU;
+    let button = [GamepadButtonType::North, GamepadButtonType::West, GamepadButtonType::East, GamepadButtonType::South][index % 4];
+    let gamepad = Gamepad::new(0);
+    app.world.send_event(GamepadEvent::Connection(GamepadConnectionEvent::new(
+        gamepad,
+        GamepadConnection::Connected(GamepadInfo { name: "test".to_string() }),
+    )));
+    app.world.send_event(GamepadEvent::Axis(GamepadAxisChangedEvent::new(gamepad, GamepadAxisType::LeftStickX, angle.sin())));
+    app.world.send_event(GamepadEvent::Axis(GamepadAxisChangedEvent::new(gamepad, GamepadAxisType::LeftStickY, angle.cos())));
+    run_frames(&mut app, 1);
+    app.world.send_event(GamepadEvent::Button(GamepadButtonChangedEvent::new(gamepad, button, 1.0)));
+    run_frames(&mut app, 2);
+
+    assert_eq!(typing_buffer(&app), letter.to_string());
+}
+
+#[test]
+fn the_combo_goes_up_after_enough_correct_letters() {
+    let mut app = headless_app(SEED);
+
+    let mut typed_letters = 0;
+    while app.world.resource::<ComboModifier>().0[Player::One] == 1 {
+        assert!(typed_letters <= 11, "the combo didn't go up after {typed_letters} correct letters");
+        let (_, word) = wait_for_plain_trash(&mut app);
+        type_letters(&mut app, &word);
+        typed_letters += word.chars().count();
+    }
+
+    assert_eq!(app.world.resource::<ComboModifier>().0[Player::One], 2);
+}
+
+#[test]
+fn the_buffer_is_cleared_when_the_marked_trash_lands() {
+    let mut app = headless_app(SEED);
+    let (entity, word) = wait_for_plain_trash(&mut app);
+
+    type_letters(&mut app, &word[..1]);
+    let landed = run_until(&mut app, MAX_WAIT_FRAMES, |world| world.get::<TrashActionActive>(entity).is_none());
+    assert!(landed, "the trash never landed");
+    run_frames(&mut app, 1);
+
+    assert_eq!(typing_buffer(&app), "");
+    assert!(app.world.get::<TrashMarked>(entity).is_none());
+}
+
+#[test]
+fn landed_trash_reaching_the_line_ends_the_run() {
+    let mut app = headless_app(SEED);
+    run_frames(&mut app, 2);
+    assert_eq!(current_state(&app), GameState::Playing);
+
+    let line = *app.world.query_filtered::<&Transform, With<GameOverLine>>().single(&app.world);
+    let data_assets = app.world.resource::<DataAssets>();
+    let kind = app.world.resource::<Assets<TrashCatalogue>>()
+        .get(&data_assets.trash_catalogue)
+        .unwrap()
+        .kinds[0]
+        .clone();
+    app.world.spawn(TrashBundle::new(Trash::new(kind, PowerUp::None))).insert(line);
+
+    assert!(run_until(&mut app, 10, |world| *world.resource::<State<GameState>>().get() == GameState::GameOver));
+}
+
+#[test]
+fn a_typed_shield_absorbs_the_next_contact_with_the_line() {
+    let mut app = headless_app(SEED);
+    let (entity, word) = wait_for_plain_trash(&mut app);
+    app.world.get_mut::<Trash>(entity).unwrap().power_up = PowerUp::Shield;
+
+    type_letters(&mut app, &word);
+    run_frames(&mut app, 1);
+    assert_eq!(app.world.resource::<ActivePowerUps>().shields, 1);
+
+    let line = *app.world.query_filtered::<&Transform, With<GameOverLine>>().single(&app.world);
+    let data_assets = app.world.resource::<DataAssets>();
+    let kind = app.world.resource::<Assets<TrashCatalogue>>()
+        .get(&data_assets.trash_catalogue)
+        .unwrap()
+        .kinds[0]
+        .clone();
+    app.world.spawn(TrashBundle::new(Trash::new(kind, PowerUp::None))).insert(line);
+
+    assert!(!run_until(&mut app, 10, |world| *world.resource::<State<GameState>>().get() == GameState::GameOver));
+    assert_eq!(app.world.resource::<ActivePowerUps>().shields, 0);
+}
+
+#[test]
+fn an_explosion_destroys_the_trash_in_its_radius_for_a_growing_bonus() {
+    let mut app = headless_app(SEED);
+    let (entity, word) = wait_for_plain_trash(&mut app);
+    app.world.get_mut::<Trash>(entity).unwrap().power_up = PowerUp::Explosion;
+    let position = *app.world.get::<Transform>(entity).unwrap();
+
+    // Two trash stacked right below the explosive one
+    let kind = app.world.get::<Trash>(entity).unwrap().kind.clone();
+    let below: Vec<Entity> = [Vec3::new(0.0, -60.0, 0.0), Vec3::new(0.0, -100.0, 0.0)].into_iter()
+        .map(|offset| {
+            app.world.spawn(TrashBundle::new(Trash::new(kind.clone(), PowerUp::None)))
+                .insert((Velocity::zero(), GravityScale(0.0), position.with_translation(position.translation + offset)))
+                .id()
+        })
+        .collect();
+
+    type_letters(&mut app, &word);
+    assert!(run_until(&mut app, 120, |world| below.iter().all(|entity| world.get_entity(*entity).is_none())));
+    run_frames(&mut app, 1);
+    // The word, then 2 and 4 for the first and second trash of the blast
+    assert!(app.world.resource::<Score>().0[Player::One] >= word.chars().count() + 6);
+}
+
+#[test]
+fn a_boss_shows_its_words_in_order_and_pays_a_bonus_when_cleared() {
+    let mut app = headless_app(SEED);
+    let (entity, word) = wait_for_plain_trash(&mut app);
+    let last_word = unused_letter(&mut app).to_string().repeat(2);
+    app.world.entity_mut(entity).insert(Boss::new(vec![word.clone(), last_word.clone()]));
+
+    type_letters(&mut app, &word);
+    run_frames(&mut app, 1);
+    let words = words_on_screen(&mut app.world);
+    assert!(words.iter().any(|(trash, _, shown)| *trash == entity && *shown == last_word));
+
+    type_letters(&mut app, &last_word);
+    run_frames(&mut app, 1);
+    assert!(app.world.get_entity(entity).is_none());
+    // Both words, then 10 per word of the boss
+    assert_eq!(app.world.resource::<Score>().0[Player::One], word.len() + last_word.len() + 20);
+}
+
+#[test]
+fn a_bin_key_after_a_boss_word_with_words_left_moves_on_to_the_next_word() {
+    let mut app = headless_app(SEED);
+    app.world.resource_mut::<Settings>().sorting_bins = true;
+    let (entity, word) = wait_for_plain_trash(&mut app);
+    let last_word = unused_letter(&mut app).to_string().repeat(2);
+    app.world.entity_mut(entity).insert(Boss::new(vec![word.clone(), last_word.clone()]));
+
+    // The last letter and the bin key come in the same frame
+    let mut letters: Vec<char> = word.chars().collect();
+    let last_letter = letters.pop().unwrap();
+    type_letters(&mut app, &letters.iter().collect::<String>());
+    app.world.send_event(TypingKey::from(KeyEdit::Letter(last_letter)));
+    app.world.send_event(TypingKey::from(KeyEdit::Letter(BIN_KEYS[0])));
+    run_frames(&mut app, 2);
+
+    let words = words_on_screen(&mut app.world);
+    assert!(words.iter().any(|(trash, _, shown)| *trash == entity && *shown == last_word));
+    assert_eq!(typing_buffer(&app), "");
+}
+
+#[test]
+fn the_same_seed_spawns_the_same_words() {
+    let mut first = headless_app(SEED);
+    let mut second = headless_app(SEED);
+    run_frames(&mut first, MAX_WAIT_FRAMES);
+    run_frames(&mut second, MAX_WAIT_FRAMES);
+
+    let words = |app: &mut App| -> Vec<String> {
+        words_on_screen(&mut app.world).into_iter().map(|(_, _, word)| word).collect()
+    };
+    assert!(!words(&mut first).is_empty());
+    assert_eq!(words(&mut first), words(&mut second));
+}
+
+#[test]
+fn a_versus_word_cleared_at_a_high_combo_drops_garbage_on_the_other_side() {
+    let mut app = headless_app(SEED);
+    app.insert_resource(GameMode::Versus);
+    let (entity, word) = wait_for_versus_word(&mut app, Player::One);
+    app.world.resource_mut::<ComboModifier>().0[Player::One] = 3;
+
+    type_characters(&mut app, &word);
+    run_frames(&mut app, 1);
+
+    assert!(app.world.get_entity(entity).is_none());
+    let score = &app.world.resource::<Score>().0;
+    assert!(score[Player::One] >= word.chars().count() * 3);
+    assert_eq!(score[Player::Two], 0);
+    let garbage = app.world
+        .query_filtered::<&Player, (With<Trash>, With<TrashActionActive>, Without<Children>)>()
+        .iter(&app.world)
+        .filter(|player| **player == Player::Two)
+        .count();
+    assert!(garbage >= 1);
+}
+
+#[test]
+fn a_wrong_versus_letter_only_resets_the_combo_of_its_player() {
+    let mut app = headless_app(SEED);
+    app.insert_resource(GameMode::Versus);
+    let (_, right_word) = wait_for_versus_word(&mut app, Player::Two);
+    let right_start: String = right_word.chars().take(1).collect();
+    type_characters(&mut app, &right_start);
+    app.world.resource_mut::<ComboModifier>().0 = PerPlayer::new(3);
+
+    // A key of player two's half that none of their words goes on with
+    let words = words_on_screen(&mut app.world).into_iter()
+        .filter(|(entity, _, _)| app.world.get::<Player>(*entity) == Some(&Player::Two))
+        .map(|(_, _, word)| word)
+        .collect::<Vec<String>>();
+    let wrong = KeyboardLayout::Qwerty.right_half().chars()
+        .find(|letter| !words.iter().any(|word| word.starts_with(&format!("{right_start}{letter}"))))
+        .unwrap();
+    type_characters(&mut app, &wrong.to_string());
+
+    assert_eq!(player_buffer(&app, Player::Two), right_start);
+    let combo_modifier = &app.world.resource::<ComboModifier>().0;
+    assert_eq!(combo_modifier[Player::Two], 1);
+    assert_eq!(combo_modifier[Player::One], 3);
+}
+
+#[test]
+fn versus_keys_go_to_the_side_of_the_layout_they_are_on() {
+    let mut app = headless_app(SEED);
+    app.insert_resource(GameMode::Versus);
+    app.world.resource_mut::<Settings>().keyboard_layout = KeyboardLayout::Azerty;
+    let (_, left_word) = wait_for_versus_word(&mut app, Player::One);
+    let (_, right_word) = wait_for_versus_word(&mut app, Player::Two);
+
+    let players = app.world.resource::<VersusPlayers>();
+    assert_eq!(players.typing('a'), Some(Player::One));
+    assert_eq!(players.typing('M'), Some(Player::Two));
+    assert_eq!(players.typing('7'), None);
+
+    let right_letter = right_word.chars().next().unwrap();
+    type_characters(&mut app, &right_letter.to_string());
+    assert_eq!(player_buffer(&app, Player::Two), right_letter.to_string());
+    assert!(player_buffer(&app, Player::One).is_empty());
+
+    let left_letter = left_word.chars().next().unwrap();
+    type_characters(&mut app, &left_letter.to_string());
+    assert_eq!(player_buffer(&app, Player::One), left_letter.to_string());
+    assert_eq!(player_buffer(&app, Player::Two), right_letter.to_string());
+}
+
+#[test]
+fn tab_and_backspace_delete_a_letter_of_the_left_and_the_right_side() {
+    let mut app = headless_app(SEED);
+    app.insert_resource(GameMode::Versus);
+    let (_, left_word) = wait_for_versus_word(&mut app, Player::One);
+    let (_, right_word) = wait_for_versus_word(&mut app, Player::Two);
+    let left_start: String = left_word.chars().take(1).collect();
+    let right_start: String = right_word.chars().take(1).collect();
+    type_characters(&mut app, &left_start);
+    type_characters(&mut app, &right_start);
+
+    press_key(&mut app, KeyCode::Tab);
+    assert!(player_buffer(&app, Player::One).is_empty());
+    assert_eq!(player_buffer(&app, Player::Two), right_start);
+
+    press_key(&mut app, KeyCode::Back);
+    assert!(player_buffer(&app, Player::Two).is_empty());
+}
+
+#[test]
+fn the_versus_side_whose_pile_reaches_its_line_loses() {
+    let mut app = headless_app(SEED);
+    app.insert_resource(GameMode::Versus);
+    run_frames(&mut app, 2);
+    assert_eq!(current_state(&app), GameState::Playing);
+
+    let right_line = *app.world.query_filtered::<(&Transform, &Player), With<GameOverLine>>()
+        .iter(&app.world)
+        .find(|(_, player)| **player == Player::Two)
+        .unwrap()
+        .0;
+    let data_assets = app.world.resource::<DataAssets>();
+    let kind = app.world.resource::<Assets<TrashCatalogue>>()
+        .get(&data_assets.trash_catalogue)
+        .unwrap()
+        .kinds[0]
+        .clone();
+    app.world.spawn(TrashBundle::new(Trash::new(kind, PowerUp::None))).insert(right_line);
+
+    assert!(run_until(&mut app, 10, |world| *world.resource::<State<GameState>>().get() == GameState::GameOver));
+    assert_eq!(app.world.resource::<VersusLoser>().0, Some(Player::Two));
+}
+
+#[test]
+fn a_word_is_never_picked_next_to_a_word_it_starts_or_ends_up_in() {
+    let available_words = AvailableWords::from_words(["cat", "cats", "ca", "cow"].map(String::from));
+    let taken_words = vec!["cat".to_string()];
+    let mut random = StdRng::seed_from_u64(SEED);
+
+    for _ in 0..20 {
+        assert_eq!(available_words.choose(0.0, 1..=10, &taken_words, &mut random), Some("cow".to_string()));
+    }
+    assert_eq!(available_words.choose(0.0, 1..=10, &["c".to_string()], &mut random), None);
+}
+
+#[test]
+fn only_the_difficulty_lengths_are_relaxed_never_the_length_limits() {
+    let available_words = AvailableWords::from_words(["cat", "hippopotamus"].map(String::from));
+    let taken_words = vec!["cat".to_string()];
+    let mut random = StdRng::seed_from_u64(SEED);
+
+    // Too long for the easiest difficulty but within the limits
+    assert_eq!(available_words.choose(0.0, 1..=12, &taken_words, &mut random), Some("hippopotamus".to_string()));
+    assert_eq!(available_words.choose(0.0, 1..=11, &taken_words, &mut random), None);
+}
+
+#[test]
+fn a_shortest_word_longer_than_the_longest_is_fixed() {
+    let mut settings = Settings {
+        min_word_length: 12,
+        max_word_length: 4,
+        ..default()
+    };
+    settings.fix_word_lengths();
+    assert_eq!((settings.min_word_length, settings.max_word_length), (12, 12));
+
+    let mut app = headless_app(SEED);
+    *app.world.resource_mut::<Settings>() = settings;
+    wait_for_plain_trash(&mut app);
+}
+
+#[test]
+fn the_miss_heatmap_follows_the_layout_and_adds_the_missed_keys_outside_of_it() {
+    let misses = [('Q', 2), ('é', 1), ('7', 3)].into_iter().collect();
+    let rows = heatmap_rows(KeyboardLayout::Azerty, &misses);
+
+    assert_eq!(rows.len(), 4);
+    assert_eq!(rows[0][0], 'a');
+    assert_eq!(rows[3], vec!['7', 'é']);
+}
@@ -1,15 +1,22 @@
-use crate::game::{Wall, Floor, spawn_trash, update_on_wrong_letter};
-use crate::game_over::{is_game_over, GameOverLine, GameOver};
-use crate::loading::TextureAssets;
+use crate::game::{Wall, Floor, create_word_text, spawn_trash, update_on_wrong_letter};
+use crate::game_mode::GameMode;
+use crate::game_over::{is_game_over, GameOverLine};
+use crate::catalogue::TrashKind;
+use crate::explosion::{Blasted, Explosion};
+use crate::events::{BossCleared, PowerUpTriggered, RunEnded, TrashLanded, TrashSorted, WordCompleted};
 use crate::GameState;
-use crate::score::{Score, ComboModifier};
+use crate::power_ups::{ActivePowerUps, PowerUp, PowerUpTrigger};
+use crate::seed::RunSeed;
+use crate::settings::Settings;
+use crate::sorting::{sorted_into, sorting_bins};
+use crate::target_lock::TargetLock;
 use crate::trash_text::{TrashText, TrashTextBundle, highlight_characters, remove_highlight};
 use bevy::prelude::*;
 use bevy::sprite::Anchor;
 use bevy::transform::TransformSystem;
 use bevy::utils::HashMap;
 use bevy_rapier2d::prelude::*;
-use crate::typing::{typing, TypingBuffer};
+use crate::typing::{Player, TypingBuffer, owner, typing};
 
 pub const WINDOW_WIDTH: f32 = 700.0;
 pub const WINDOW_HEIGHT: f32 = 800.0;
@@ -25,6 +32,10 @@ const TRASH_SPAWN_DISTANCE_BETWEEN_SPAWNS: f32 = 30.0;
 const INITIAL_TRASH_SPAWN_RATE: f32 = 2.0;
 const INITIAL_DIFICULTY_INCREASE_RATE: f32 = 10.0;
 
+const BOSS_PROGRESS_WIDTH: f32 = 80.0;
+const BOSS_PROGRESS_COLOR: Color = Color::rgb(0.3, 0.9, 0.3);
+const BOSS_UPCOMING_COLOR: Color = Color::rgb(0.7, 0.7, 0.7);
+
 pub struct TrashPlugin;
 
 
@@ -35,60 +46,20 @@ pub enum RunState {
 }
 
 
-#[derive(Clone, Debug)]
-pub enum TrashType {
-    Bottle,
-    Pizza,
-    BigBox,
-    GlassBottle,
-    News,
-    Shampoo,
-    SmallCan,
-    Soda,
-    Spray,
-}
-
-impl Default for TrashType {
-    fn default() -> Self {
-        Self::Bottle
-    }
-}
-
-#[derive(Clone, Debug, PartialEq)]
-pub enum PowerUp {
-    None,
-    Explosion,
-    Link
-}
-
-impl Default for PowerUp {
-    fn default() -> Self {
-        Self::None
-    }
-}
-
 #[derive(PartialEq, Clone, Debug)]
 pub enum PowerUpEvent {
     None,
     EntityDestroyed,
-    DestroyLinked,
 }
 
 
 #[derive(Component, Debug, Clone)]
 pub struct Trash {
-    pub trash_type: TrashType,
+    pub kind: TrashKind,
     pub power_up: PowerUp,
-    pub size: Vec2,
     pub activated: bool,
 }
 
-impl Default for Trash {
-    fn default() -> Self {
-        Self::bottle()
-    }
-}
-
 
 #[derive(Component, Debug, Clone, Default)]
 pub struct BufferText;
@@ -103,8 +74,25 @@ pub struct TrashActionDuplicate;
 #[derive(Component, Default)]
 pub struct TrashMarked;
 
-#[derive(Component, Default)]
-pub struct TrashExplosion;
+/// A large trash with a sequence of words to type in order, its `TrashText` is the next one
+#[derive(Component, Clone, Debug)]
+pub struct Boss {
+    pub words: Vec<String>,
+    /// How many of the words were typed
+    pub typed: usize,
+}
+
+/// The bar above a boss, filled with the part of its words that was typed
+#[derive(Component)]
+struct BossProgress;
+
+/// The words of a boss after the one being typed
+#[derive(Component)]
+struct BossUpcoming;
+
+/// Trash spawned together with the `PowerUp::Link` power up, typing any word of the group clears all of it
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct LinkedTrash(pub u32);
 
 
 // #[derive(Resource)]
@@ -136,17 +124,22 @@ pub struct TrashBundle {
 }
 
 impl TrashBundle {
-    pub fn new(sprite: Handle<Image>, trash: Trash) -> Self {
+    pub fn new(trash: Trash) -> Self {
+        let size = trash.kind.size();
         Self {
             sprite: SpriteBundle {
-                texture: sprite,
+                sprite: Sprite {
+                    custom_size: trash.kind.sprite_size(),
+                    ..default()
+                },
+                texture: trash.kind.texture_handle.clone(),
                 ..default()
             },
             rigidbody: RigidBody::Dynamic,
             velocity: Velocity::linear(TRASH_STARTING_VELOCITY),
-            collider: Collider::cuboid(trash.size.x, trash.size.y),
-            collider_mass_properties: ColliderMassProperties::Mass(1.0),
-            restitution: Restitution::coefficient(0.2),
+            collider: Collider::cuboid(size.x, size.y),
+            collider_mass_properties: ColliderMassProperties::Mass(trash.kind.mass),
+            restitution: Restitution::coefficient(trash.kind.restitution),
             active_events: ActiveEvents::COLLISION_EVENTS,
             trash: trash.clone(),
         }
@@ -174,14 +167,14 @@ impl Plugin for TrashPlugin {
         app.add_systems(OnEnter(GameState::Playing), setup)
         .add_systems(Update, (
                 // spawn_trash.run_if(in_state(GameState::Playing)),
-                trash_power_ups_effects.after(spawn_trash),
+                draw_link_tethers.run_if(in_state(GameState::Playing)),
                 // update_difficuly.after(setup).run_if(in_state(GameState::Playing)),
                 // typing.after(setup).run_if(in_state(GameState::Playing)),
                 activate_matching_trash.after(update_on_wrong_letter).before(handle_trash_collision),
                 highlight_character.after(update_on_wrong_letter),
+                update_boss_progress.after(activate_matching_trash).run_if(in_state(GameState::Playing)),
                 handle_trash_collision.after(activate_matching_trash).run_if(in_state(GameState::Playing)),
                 clamp_duplicated_trash.after(handle_trash_collision),
-                remove_explosions.after(handle_trash_collision),
                 // update_buffer_text.after(typing),
                 // click_restart_button.run_if(in_state(GameState::GameOver)),
             )
@@ -199,120 +192,76 @@ fn setup() {
 }
 
 impl Trash {
-    pub fn get_by_type(trash_type: TrashType) -> Self {
-        match trash_type {
-            TrashType::Bottle => Self::bottle(),
-            TrashType::Pizza => Self::pizza(),
-            TrashType::News => Self::news(),
-            TrashType::Shampoo => Self::shampoo(),
-            TrashType::SmallCan => Self::small_can(),
-            TrashType::Soda => Self::soda(),
-            TrashType::Spray => Self::spray(),
-            TrashType::BigBox => Self::big_box(),
-            TrashType::GlassBottle => Self::glass_bottle(),
-        }
-    }
-
-    pub fn bottle() -> Self {
-        Self {
-            trash_type: TrashType::Bottle,
-            size: Vec2::new(15.0, 16.0),
-            power_up: PowerUp::None,
-            activated: false,
-        }
-    }
-
-    pub fn pizza() -> Self {
+    pub fn new(kind: TrashKind, power_up: PowerUp) -> Self {
         Self {
-            trash_type: TrashType::Pizza,
-            size: Vec2::new(32.0, 16.0), // 60x30
-            power_up: PowerUp::None,
-            activated: false,
-        }
-    }
-
-    pub fn big_box() -> Self {
-        Self {
-            trash_type: TrashType::BigBox,
-            size: Vec2::new(25.0, 24.0), // 51x48
-            power_up: PowerUp::None,
-            activated: false,
-        }
-    }
-
-    pub fn glass_bottle() -> Self {
-        Self {
-            trash_type: TrashType::GlassBottle,
-            size: Vec2::new(8.0, 25.0), // 17x50
-            power_up: PowerUp::None,
+            kind,
+            power_up,
             activated: false,
         }
     }
+}
 
-    pub fn news() -> Self {
-        Self {
-            trash_type: TrashType::News,
-            size: Vec2::new(26.0, 16.0), // 53x28
-            power_up: PowerUp::None,
-            activated: false,
-        }
+impl Boss {
+    pub fn new(words: Vec<String>) -> Self {
+        Self { words, typed: 0 }
     }
 
-    pub fn shampoo() -> Self {
-        Self {
-            trash_type: TrashType::Shampoo,
-            size: Vec2::new(17.0, 22.0), // 36x44
-            power_up: PowerUp::None,
-            activated: false,
-        }
+    /// The words left after the one on screen
+    pub fn upcoming(&self) -> &[String] {
+        self.words.get(self.typed + 1..).unwrap_or_default()
     }
 
-    pub fn small_can() -> Self {
-        Self {
-            trash_type: TrashType::SmallCan,
-            size: Vec2::new(11.0, 15.0), // 23x30
-            power_up: PowerUp::None,
-            activated: false,
-        }
-    }
-
-    pub fn soda() -> Self {
-        Self {
-            trash_type: TrashType::Soda,
-            size: Vec2::new(9.0, 17.0), // 18x34
-            power_up: PowerUp::None,
-            activated: false,
-        }
-    }
-
-    pub fn spray() -> Self {
-        Self {
-            trash_type: TrashType::Spray,
-            size: Vec2::new(8.0, 20.0), // 17x40
-            power_up: PowerUp::None,
-            activated: false,
-        }
+    fn progress(&self) -> f32 {
+        self.typed as f32 / self.words.len() as f32
     }
 }
 
-
-pub fn get_trash_sprite(trash_type: &TrashType, textures: &Res<TextureAssets>) -> Handle<Image> {
-    match trash_type {
-        TrashType::Bottle => textures.bottle.clone(),
-        TrashType::Pizza => textures.pizza.clone(),
-        TrashType::BigBox => textures.big_box.clone(),
-        TrashType::GlassBottle => textures.glass_bottle.clone(),
-        TrashType::News => textures.news.clone(),
-        TrashType::Shampoo => textures.shampoo.clone(),
-        TrashType::SmallCan => textures.small_can.clone(),
-        TrashType::Soda => textures.soda.clone(),
-        TrashType::Spray => textures.spray.clone(),
-    }
+/// The progress bar and the upcoming words, above a boss of the given half height
+pub fn spawn_boss_progress(parent: &mut ChildBuilder, boss: &Boss, half_height: f32) {
+    parent.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::BLACK.with_a(0.6),
+                custom_size: Some(Vec2::new(BOSS_PROGRESS_WIDTH, 8.0)),
+                ..default()
+            },
+            transform: Transform::from_xyz(0.0, half_height + 12.0, 1.0),
+            ..default()
+        },
+    ));
+    parent.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: BOSS_PROGRESS_COLOR,
+                custom_size: Some(Vec2::new(0.0, 8.0)),
+                anchor: Anchor::CenterLeft,
+                ..default()
+            },
+            transform: Transform::from_xyz(-BOSS_PROGRESS_WIDTH / 2.0, half_height + 12.0, 2.0),
+            ..default()
+        },
+        BossProgress,
+    ));
+    parent.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                boss.upcoming().join(" "),
+                TextStyle {
+                    font_size: 18.0,
+                    color: BOSS_UPCOMING_COLOR,
+                    ..default()
+                },
+            ),
+            transform: Transform::from_xyz(0.0, half_height + 30.0, 1.0),
+            ..default()
+        },
+        BossUpcoming,
+    ));
 }
 
 
-
 // https://github.com/bevyengine/bevy/issues/1780#issuecomment-1760929069
+#[allow(clippy::type_complexity)]
 fn fix_trash_label_rotation(
     mut text_query: Query<(&Parent, &mut Transform), With<TrashText>>,
     query_parents: Query<&Transform, (With<Trash>, Without<TrashText>, With<TrashActionActive>)>,
@@ -335,78 +284,73 @@ fn remove_all_marked_trash(
 }
 
 
-fn trash_power_ups_effects(
-    mut active_trash_query: Query<(&Trash, &mut Sprite), With<TrashActionActive>>,
-    mut inactive_trash_query: Query<&mut Sprite, Without<TrashActionActive>>,
-    time: Res<Time>,
+fn draw_link_tethers(
+    mut gizmos: Gizmos,
+    linked_trash_query: Query<(&Transform, &LinkedTrash), With<TrashActionActive>>,
 ) {
-    let seconds = time.elapsed_seconds();
-    let color_change_interval = (3.5 * seconds).sin() / 2.0 + 0.5;
-
-    for (trash, mut sprite) in &mut active_trash_query.iter_mut() {
-        match trash.power_up {
-            PowerUp::Explosion => {
-                sprite.color = Color::rgb(
-                    1.0,
-                    color_change_interval,
-                    color_change_interval
-                );
-            },
-            PowerUp::Link => {
-                sprite.color = Color::rgb(
-                    color_change_interval,
-                    color_change_interval,
-                    1.0,
-                );
-            },
-            _ => {}
+    let mut groups: HashMap<LinkedTrash, Vec<Vec2>> = HashMap::new();
+    for (transform, linked_trash) in linked_trash_query.iter() {
+        groups.entry(*linked_trash).or_default().push(transform.translation.truncate());
+    }
+
+    for positions in groups.values_mut() {
+        positions.sort_by(|a, b| a.x.total_cmp(&b.x));
+        for pair in positions.windows(2) {
+            gizmos.line_2d(pair[0], pair[1], Color::rgb(0.3, 0.5, 1.0));
         }
     }
+}
 
-    for mut sprite in &mut inactive_trash_query.iter_mut() {
-        if sprite.color != Color::WHITE {
-            sprite.color = Color::WHITE;
+fn update_boss_progress(
+    boss_query: Query<(&Boss, &Children), Changed<Boss>>,
+    mut progress_query: Query<&mut Sprite, With<BossProgress>>,
+    mut upcoming_query: Query<&mut Text, With<BossUpcoming>>,
+) {
+    for (boss, children) in boss_query.iter() {
+        for child in children.iter() {
+            if let Ok(mut sprite) = progress_query.get_mut(*child) {
+                sprite.custom_size = Some(Vec2::new(BOSS_PROGRESS_WIDTH * boss.progress(), 8.0));
+            }
+            if let Ok(mut text) = upcoming_query.get_mut(*child) {
+                text.sections[0].value = boss.upcoming().join(" ");
+            }
         }
     }
 }
 
 
 fn highlight_character(
-    mut trash_query: Query<(&TrashText, &mut Text)>,
+    mut trash_query: Query<(&Parent, &TrashText, &mut Text)>,
+    player_query: Query<&Player>,
     typing_buffer: Res<TypingBuffer>,
+    target_lock: Res<TargetLock>,
 ) {
-    for (trash_text, mut ui_text) in &mut trash_query.iter_mut() {
-        if trash_text.word.starts_with(&typing_buffer.0) {
-            ui_text.sections = highlight_characters(&ui_text.sections, typing_buffer.0.len(), trash_text.highlight_color)
+    for (parent, trash_text, mut ui_text) in &mut trash_query.iter_mut() {
+        let typed = &typing_buffer.0[owner(&player_query, parent.get())];
+        if target_lock.allows(parent.get()) && trash_text.word.starts_with(typed.as_str()) {
+            ui_text.sections = highlight_characters(&ui_text.sections, typed.chars().count(), trash_text.highlight_color)
         } else {
             ui_text.sections = remove_highlight(&ui_text.sections, trash_text.color)
         }
     }
 }
 
-fn remove_explosions(
-    mut commands: Commands,
-    explosion_query: Query<Entity, With<TrashExplosion>>,
-) {
-    for entity in &mut explosion_query.iter() {
-        commands.entity(entity).despawn_recursive();
-    }
-}
-
 
 fn remove_trash_text(commands: &mut Commands, trash_entity: &Entity) {
     commands.entity(*trash_entity).remove::<TrashActionActive>();
     commands.entity(*trash_entity).remove::<TrashMarked>();
+    commands.entity(*trash_entity).remove::<LinkedTrash>();
+    commands.entity(*trash_entity).remove::<Boss>();
     commands.entity(*trash_entity).despawn_descendants();
 
 }
 
-fn create_duplicated_trash_from_entity(commands: &mut Commands, sprite: Handle<Image>, trash: Trash, transform: Transform) {
+fn create_duplicated_trash_from_entity(commands: &mut Commands, trash: Trash, transform: Transform) {
     let mut trash = trash.clone();
     trash.power_up = PowerUp::None;
     trash.activated = false;
 
-    let mut bundle = TrashBundle::new(sprite, trash);
+    let mut bundle = TrashBundle::new(trash);
     bundle.velocity = Velocity::zero();
 
     commands.spawn(bundle)
@@ -414,6 +358,17 @@ fn create_duplicated_trash_from_entity(commands: &mut Commands, sprite: Handle<I
         .insert(GravityScale(40.0));
 }
 
+/// A boss lands as a heavy pile, a copy of it falls on top for every upcoming word it still had
+fn land_boss(commands: &mut Commands, boss: &Boss, trash: &Trash, transform: &Transform) {
+    let height = trash.kind.size().y * 2.0;
+    for index in 0..boss.upcoming().len() {
+        let mut transform = *transform;
+        transform.translation.y += height * (index + 1) as f32;
+        create_duplicated_trash_from_entity(commands, trash.clone(), transform);
+    }
+}
+
+#[allow(clippy::type_complexity)]
 fn should_delete_text(
     entity: &Entity,
     other: &Entity,
@@ -448,7 +403,7 @@ fn should_delete_text(
 fn should_duplicate_trash(
     entity: &Entity,
     other: &Entity,
-    duplicate_trash_query: &Query<(Entity, &Trash, &Transform, &Handle<Image>), With<TrashActionDuplicate>>,
+    duplicate_trash_query: &Query<(Entity, &Trash, &Transform), With<TrashActionDuplicate>>,
     walls_query: &Query<Entity, With<Wall>>,
     floor_query: &Query<Entity, With<Floor>>,
     game_over_query: &Query<Entity, With<GameOverLine>>,
@@ -466,66 +421,39 @@ fn should_duplicate_trash(
 }
 
 
-fn should_explode(
-    entity: &Entity,
-    other: &Entity,
-    explosion_query: &Query<&Transform, With<TrashExplosion>>,
-    trash_query: &Query<Entity, With<Trash>>
-) -> bool {
-
-    if let Ok(_) = explosion_query.get(*other) {
-        if trash_query.get(*entity).is_ok() {
-            return true;
-        }
-    }
-
-    false
-}
-
-
 fn handle_power_up_event(
     entity: &Entity,
-    commands: &mut Commands,
+    power_up_triggered_events: &mut EventWriter<PowerUpTriggered>,
     trash_query: &Query<(Entity, &Velocity, &Trash, &Transform), With<TrashActionActive>>,
 ) -> PowerUpEvent {
     if let Ok((_, _, trash, transform)) = trash_query.get(*entity) {
-        if trash.activated {
-            match trash.power_up {
-                PowerUp::Explosion => {
-                    commands.spawn(
-                        SpriteBundle {
-                            ..default()
-                        }
-                    )
-                    .insert(transform.clone())
-                    .insert(Collider::cuboid(50.0, 50.0))
-                    .insert(Sensor)
-                    .insert(TrashExplosion);
-
-                    return PowerUpEvent::EntityDestroyed;
-                },
-                PowerUp::Link => {
-                    return PowerUpEvent::DestroyLinked;
-                },
-                _ => {}
-            }
+        if trash.activated && trash.power_up.trigger() == PowerUpTrigger::Impact {
+            power_up_triggered_events.send(PowerUpTriggered {
+                power_up: trash.power_up,
+                trash: *entity,
+                position: transform.translation.truncate(),
+            });
+
+            return PowerUpEvent::EntityDestroyed;
         }
     }
 
     PowerUpEvent::None
 }
 
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 fn handle_trash_entity_collision(
     entity: &Entity,
     other: &Entity,
     commands: &mut Commands,
-    typing_buffer: &mut ResMut<TypingBuffer>,
+    landed_events: &mut EventWriter<TrashLanded>,
+    power_up_triggered_events: &mut EventWriter<PowerUpTriggered>,
     active_trash_query: &Query<(Entity, &Velocity, &Trash, &Transform), With<TrashActionActive>>,
     inactive_trash_query: &Query<(Entity, &Velocity), (Without<TrashActionActive>, With<Trash>)>,
-    duplicate_trash_query: &Query<(Entity, &Trash, &Transform, &Handle<Image>), With<TrashActionDuplicate>>,
+    duplicate_trash_query: &Query<(Entity, &Trash, &Transform), With<TrashActionDuplicate>>,
     marked_trash_query: &Query<Entity, With<TrashMarked>>,
-    explosion_query: &Query<&Transform, With<TrashExplosion>>,
-    all_trash_query: &Query<Entity, With<Trash>>,
+    player_query: &Query<&Player>,
+    boss_query: &Query<(&Boss, &Trash, &Transform)>,
     walls_query: &Query<Entity, With<Wall>>,
     floor_query: &Query<Entity, With<Floor>>,
     game_over_query: &Query<Entity, With<GameOverLine>>,
@@ -533,22 +461,15 @@ fn handle_trash_entity_collision(
 ) {
     let mut powerup_event = PowerUpEvent::None;
     if game_over_query.get(*entity).is_err() && game_over_query.get(*other).is_err() {
-        powerup_event = handle_power_up_event(entity, commands, active_trash_query);
-    }
-
-    if should_explode(entity, other, explosion_query, all_trash_query) {
-        if marked_trash_query.get(*entity).is_ok() {
-            typing_buffer.0 = "".to_string();
-        }
-        commands.entity(*entity).despawn_recursive();
-        return;
+        powerup_event = handle_power_up_event(entity, power_up_triggered_events, active_trash_query);
     }
 
     let mut should_remove_text = false;
     if should_delete_text(entity, other, active_trash_query, inactive_trash_query, walls_query, floor_query) {
-        if marked_trash_query.get(*entity).is_ok() {
-            typing_buffer.0 = "".to_string();
-        }
+        landed_events.send(TrashLanded {
+            player: owner(player_query, *entity),
+            marked: marked_trash_query.get(*entity).is_ok(),
+        });
         should_remove_text = true;
 
     }
@@ -560,40 +481,74 @@ fn handle_trash_entity_collision(
         transform.translation.y += 10.0;
         create_duplicated_trash_from_entity(
             commands,
-            trash_data.3.clone(),
             trash_data.1.clone(),
             trash_data.2.clone());
     }
 
 
     if powerup_event != PowerUpEvent::EntityDestroyed && should_remove_text {
+        if let Ok((boss, trash, transform)) = boss_query.get(*entity) {
+            land_boss(commands, boss, trash, transform);
+        }
         remove_trash_text(commands, entity);
     }
 }
 
 
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn handle_trash_collision(
     mut commands: Commands,
-    mut typing_buffer: ResMut<TypingBuffer>,
     mut collision_events: EventReader<CollisionEvent>,
-    mut next_state: ResMut<NextState<GameState>>,
+    mut landed_events: EventWriter<TrashLanded>,
+    mut power_up_triggered_events: EventWriter<PowerUpTriggered>,
+    mut run_ended_events: EventWriter<RunEnded>,
     active_trash_query: Query<(Entity, &Velocity, &Trash, &Transform), With<TrashActionActive>>,
     inactive_trash_query: Query<(Entity, &Velocity), (Without<TrashActionActive>, With<Trash>)>,
-    duplicate_trash_query: Query<(Entity, &Trash, &Transform, &Handle<Image>), With<TrashActionDuplicate>>,
+    duplicate_trash_query: Query<(Entity, &Trash, &Transform), With<TrashActionDuplicate>>,
     marked_trash_query: Query<Entity, With<TrashMarked>>,
-    explosion_query: Query<&Transform, With<TrashExplosion>>,
-    all_trash_query: Query<Entity, With<Trash>>,
+    player_query: Query<&Player>,
+    boss_query: Query<(&Boss, &Trash, &Transform)>,
     walls_query: Query<Entity, With<Wall>>,
     floor_query: Query<Entity, With<Floor>>,
     game_over_query: Query<Entity, With<GameOverLine>>,
+    explosion_query: Query<(), With<Explosion>>,
+    game_mode: Res<GameMode>,
+    mut active_power_ups: ResMut<ActivePowerUps>,
 ) {
+    let mut swept = false;
     for collision_event in collision_events.read() {
         match collision_event {
             CollisionEvent::Started(entity1, entity2, _) => {
+                // `ExplosionPlugin` handles what the blasts overlap
+                if explosion_query.contains(*entity1) || explosion_query.contains(*entity2) {
+                    continue;
+                }
+
 
                 if is_game_over(entity1, entity2, &inactive_trash_query, &game_over_query) {
-                    commands.spawn(GameOver);
-                    next_state.set(GameState::GameOver);
+                    // A shield takes the hit, the trash that reached the line goes with it
+                    if active_power_ups.shields > 0 {
+                        active_power_ups.shields -= 1;
+                        for entity in [entity1, entity2] {
+                            if inactive_trash_query.get(*entity).is_ok() {
+                                commands.entity(*entity).despawn_recursive();
+                            }
+                        }
+                        continue;
+                    }
+
+                    // Zen runs don't end, the pile is swept away (once) instead and the other collisions still count
+                    if *game_mode == GameMode::Zen {
+                        if !swept {
+                            for (entity, _) in inactive_trash_query.iter() {
+                                commands.entity(entity).despawn_recursive();
+                            }
+                            swept = true;
+                        }
+                        continue;
+                    }
+
+                    run_ended_events.send(RunEnded);
                     return;
                 }
 
@@ -601,13 +556,14 @@ pub fn handle_trash_collision(
                     entity1,
                     entity2,
                     &mut commands,
-                    &mut typing_buffer,
+                    &mut landed_events,
+                    &mut power_up_triggered_events,
                     &active_trash_query,
                     &inactive_trash_query,
                     &duplicate_trash_query,
                     &marked_trash_query,
-                    &explosion_query,
-                    &all_trash_query,
+                    &player_query,
+                    &boss_query,
                     &walls_query,
                     &floor_query,
                     &game_over_query
@@ -617,13 +573,14 @@ pub fn handle_trash_collision(
                     entity2,
                     entity1,
                     &mut commands,
-                    &mut typing_buffer,
+                    &mut landed_events,
+                    &mut power_up_triggered_events,
                     &active_trash_query,
                     &inactive_trash_query,
                     &duplicate_trash_query,
                     &marked_trash_query,
-                    &explosion_query,
-                    &all_trash_query,
+                    &player_query,
+                    &boss_query,
                     &walls_query,
                     &floor_query,
                     &game_over_query
@@ -635,8 +592,9 @@ pub fn handle_trash_collision(
 }
 
 
+#[allow(clippy::type_complexity)]
 fn clamp_duplicated_trash(
-    mut trash_query: Query<&mut Velocity, (With<Trash>, Without<TrashActionActive>)>,
+    mut trash_query: Query<&mut Velocity, (With<Trash>, Without<TrashActionActive>, Without<Blasted>)>,
 ) {
     for mut velocity in trash_query.iter_mut() {
         if velocity.linvel.length() > TRASH_STARTING_VELOCITY.length() / 2.0 {
@@ -650,13 +608,23 @@ fn clamp_duplicated_trash(
 }
 
 
+#[allow(clippy::too_many_arguments)]
 fn activate_matching_trash(
     mut commands: Commands,
-    trash_text_query: Query<(&Parent, &Transform, &TrashText)>,
+    trash_text_query: Query<(Entity, &Parent, &Transform, &TrashText)>,
     mut trash_query: Query<(&mut Trash, &Transform), With<TrashActionActive>>,
-    mut typing_buffer: ResMut<TypingBuffer>,
-    mut score: ResMut<Score>,
-    combo_modifier: Res<ComboModifier>,
+    mut boss_query: Query<&mut Boss>,
+    linked_trash_query: Query<(Entity, &LinkedTrash, &Children), With<TrashActionActive>>,
+    words_query: Query<&TrashText>,
+    player_query: Query<&Player>,
+    typing_buffer: Res<TypingBuffer>,
+    target_lock: Res<TargetLock>,
+    settings: Res<Settings>,
+    run_seed: Res<RunSeed>,
+    mut word_completed_events: EventWriter<WordCompleted>,
+    mut boss_cleared_events: EventWriter<BossCleared>,
+    mut trash_sorted_events: EventWriter<TrashSorted>,
+    mut power_up_triggered_events: EventWriter<PowerUpTriggered>,
 ) {
 
     if !typing_buffer.is_changed() {
@@ -664,36 +632,92 @@ fn activate_matching_trash(
     }
 
     // let mut trash_to_destroy: Vec<&Parent> = Vec::new();
-    let mut should_clear_buffer = false;
-
-    for (entity, _transform, trash_text) in &mut trash_text_query.iter() {
-        if typing_buffer.0 == trash_text.word {
+    let mut linked_groups_to_clear: Vec<LinkedTrash> = Vec::new();
+    let sorting = sorting_bins(&settings, &run_seed);
+
+    for (text_entity, entity, _transform, trash_text) in &mut trash_text_query.iter() {
+        let player = owner(&player_query, entity.get());
+        let completed = typing_buffer.0[player] == trash_text.word;
+        let bin = if sorting { sorted_into(&trash_text.word, &typing_buffer.0[player]) } else { None };
+        if (completed || bin.is_some()) && target_lock.allows(entity.get()) {
             if let Ok(mut trash) = trash_query.get_mut(entity.get()) {
                 if trash.0.activated {
                     continue;
                 }
 
-                match trash.0.power_up {
-                    PowerUp::Explosion => {
-                        commands.entity(entity.get()).remove::<TrashMarked>();
-                        commands.entity(entity.get()).despawn_descendants();
-                        // remove_trash_text(&mut commands, entity);
-                        // commands.spawn(TrashExplosion)
-                        //     .insert(trash.1.clone());
-                    },
-                    PowerUp::Link => {
-                        commands.entity(entity.get()).remove::<TrashMarked>();
-                        commands.entity(entity.get()).despawn_descendants();
-
-                    },
-                    PowerUp::None => {
-                        commands.entity(entity.get()).despawn_recursive();
+                // A boss only goes with its last word, the others are swapped for the next one.
+                // Only the last word goes into a bin, a bin key after the others is ignored
+                if let Ok(mut boss) = boss_query.get_mut(entity.get()) {
+                    if !boss.upcoming().is_empty() {
+                        boss.typed += 1;
+                        let next_word = boss.words[boss.typed].clone();
+                        commands.entity(text_entity).despawn_recursive();
+                        commands.entity(entity.get()).remove::<TrashMarked>().with_children(|parent| {
+                            parent.spawn(create_word_text(&settings, next_word));
+                        });
+                        word_completed_events.send(WordCompleted {
+                            player,
+                            word: trash_text.word.clone(),
+                        });
+                        continue;
                     }
                 }
 
+                // Power ups that go off on impact still do with the word alone, the rest waits for a bin key
+                let goes_off_on_impact = trash.0.power_up.trigger() == PowerUpTrigger::Impact;
+                if sorting && !goes_off_on_impact && bin.is_none() {
+                    continue;
+                }
+
+                if trash.0.power_up == PowerUp::Link {
+                    // The whole group (including this trash) is scored and cleared below,
+                    // only the typed trash goes into the bin, the others don't have a bin key of their own
+                    if let Ok((_, linked_trash, _)) = linked_trash_query.get(entity.get()) {
+                        linked_groups_to_clear.push(*linked_trash);
+                        if let Some(bin) = bin {
+                            trash_sorted_events.send(TrashSorted {
+                                player,
+                                material: trash.0.kind.material,
+                                bin,
+                            });
+                        }
+                    }
+                    continue;
+                }
+
+                if goes_off_on_impact {
+                    // It keeps falling without its word until it hits something
+                    commands.entity(entity.get()).remove::<TrashMarked>();
+                    commands.entity(entity.get()).despawn_descendants();
+                } else {
+                    commands.entity(entity.get()).despawn_recursive();
+                    if trash.0.power_up != PowerUp::None {
+                        power_up_triggered_events.send(PowerUpTriggered {
+                            power_up: trash.0.power_up,
+                            trash: entity.get(),
+                            position: trash.1.translation.truncate(),
+                        });
+                    }
+                }
+
+                if let Some(bin) = bin {
+                    trash_sorted_events.send(TrashSorted {
+                        player,
+                        material: trash.0.kind.material,
+                        bin,
+                    });
+                }
+
                 trash.0.activated = true;
-                score.0 += trash_text.word.len() * combo_modifier.0;
-                should_clear_buffer = true;
+                word_completed_events.send(WordCompleted {
+                    player,
+                    word: trash_text.word.clone(),
+                });
+                if let Ok(boss) = boss_query.get(entity.get()) {
+                    boss_cleared_events.send(BossCleared {
+                        words: boss.words.len(),
+                    });
+                }
             }
             // trash_to_destroy.push(entity);
         }
@@ -702,7 +726,21 @@ fn activate_matching_trash(
     //     commands.entity(entity.get()).despawn_recursive();
     // }
 
-    if should_clear_buffer {
-        typing_buffer.0 = "".to_string();
+    for (entity, linked_trash, children) in linked_trash_query.iter() {
+        if !linked_groups_to_clear.contains(linked_trash) {
+            continue;
+        }
+
+        let player = owner(&player_query, entity);
+
+        for child in children.iter() {
+            if let Ok(trash_text) = words_query.get(*child) {
+                word_completed_events.send(WordCompleted {
+                    player,
+                    word: trash_text.word.clone(),
+                });
+            }
+        }
+        commands.entity(entity).despawn_recursive();
     }
 }
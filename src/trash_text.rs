@@ -1,6 +1,8 @@
 use bevy::prelude::*;
 use bevy::sprite::Anchor;
 
+// Phrases longer than this are wrapped on the spaces between their words
+const MAX_LINE_LENGTH: usize = 20;
 
 // pub struct TrashTextPlugin;
 
@@ -51,16 +53,12 @@ pub struct TrashTextBundle {
 // }
 
 
+/// Every character of the word is a section, the spaces the text is wrapped on are line breaks in place of the space.
+/// The index of a character is the same on every line so the highlight carries over to the next lines.
 pub fn highlight_characters(sections: &Vec<TextSection>, max_character_index: usize, highlight_color: Color) -> Vec<TextSection> {
     let mut sections = sections.clone();
-    for i in 0..max_character_index {
-        let section = sections.get_mut(i).unwrap();
-        section.style.color = highlight_color;
-    }
-
-    for i in max_character_index..sections.len() {
-        let section = sections.get_mut(i).unwrap();
-        section.style.color = Color::WHITE;
+    for (index, section) in sections.iter_mut().enumerate() {
+        section.style.color = if index < max_character_index { highlight_color } else { Color::WHITE };
     }
     // let section = sections.get_mut(character_index).unwrap();
     // section.style.color = self.highlight_color;
@@ -90,7 +88,7 @@ impl TrashTextBundle {
             // color: style.color,
                 // TrashText::new(text.clone(), highlight_color),
             ui: Text2dBundle {
-                text: Text::from_sections(sections),
+                text: Text::from_sections(sections).with_alignment(TextAlignment::Center),
                 text_anchor: anchor,
                 // transform: Transform::from_translation(Vec3::new(0.0, 0.0, 1.0)),
                 ..default()
@@ -100,10 +98,12 @@ impl TrashTextBundle {
 
     fn create_sections_from_text(text: &String, style: &TextStyle) -> Vec<TextSection> {
         let mut sections = Vec::new();
+        let line_breaks = line_breaks(text);
 
-        for character in text.chars() {
+        for (index, character) in text.chars().enumerate() {
+            let value = if line_breaks.contains(&index) { "\n".to_string() } else { character.to_string() };
             sections.push(TextSection {
-                value: character.to_string(),
+                value,
                 style: style.clone()
             })
         }
@@ -131,3 +131,27 @@ impl TrashTextBundle {
     //     self.ui.text.sections = sections;
     // }
 }
+
+
+/// The indices of the spaces the text is wrapped on so its lines stay under `MAX_LINE_LENGTH`,
+/// a word longer than a line keeps going until the next space
+pub fn line_breaks(text: &str) -> Vec<usize> {
+    let mut breaks = Vec::new();
+    let mut line_start = 0;
+    let mut last_space = None;
+
+    for (index, character) in text.chars().enumerate() {
+        if character == ' ' {
+            last_space = Some(index);
+        }
+
+        if index - line_start >= MAX_LINE_LENGTH {
+            if let Some(space) = last_space.filter(|space| *space > line_start) {
+                breaks.push(space);
+                line_start = space + 1;
+            }
+        }
+    }
+
+    breaks
+}
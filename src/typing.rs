@@ -1,17 +1,58 @@
-use crate::GameState;
-use crate::trash_text::TrashText;
+use std::ops::{Index, IndexMut};
+
+use crate::{GameState, PauseState};
+use crate::events::{TrashDestroyed, TrashLanded, WordCompleted};
+use crate::game_mode::{GameMode, is_solo};
+use crate::replay::is_live;
+use crate::target_lock::TargetLock;
+use crate::trash::handle_trash_collision;
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
 pub struct TypingPlugin;
 
-#[derive(Resource)]
-pub struct TypingBuffer(pub String);
+/// Who a key, a typing buffer or a trash belongs to. Solo runs only have `Player::One`,
+/// in versus runs player one plays on the left and player two on the right.
+/// Only the trash of a versus run has the component, the rest belongs to `Player::One`.
+#[derive(Component, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum Player {
+    #[default]
+    One,
+    Two,
+}
+
+/// A value for each player, solo runs only use the one of `Player::One`
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct PerPlayer<T>([T; 2]);
+
+#[derive(Resource, Default)]
+pub struct TypingBuffer(pub PerPlayer<String>);
+
+/// A single edit of a typing buffer, this is what gets recorded in replays
+#[derive(Event, Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct TypingKey {
+    pub player: Player,
+    pub edit: KeyEdit,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum KeyEdit {
+    Letter(char),
+    Backspace,
+    Clear,
+    /// A tap on a trash, clears the buffer and locks the trash at this world position
+    Target { x: f32, y: f32 },
+}
 
 impl Plugin for TypingPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(TypingBuffer("".to_string()))
+        app.init_resource::<TypingBuffer>()
+        .add_event::<TypingKey>()
+        .add_systems(OnEnter(GameState::Playing), reset_typing_buffer)
         .add_systems(Update, (
-                typing.run_if(in_state(GameState::Playing)),
+                read_typing_keys.before(typing).run_if(in_state(GameState::Playing)).run_if(in_state(PauseState::Running)).run_if(is_live).run_if(is_solo),
+                typing.run_if(in_state(GameState::Playing)).run_if(in_state(PauseState::Running)),
+                clear_typing_buffer.after(handle_trash_collision).run_if(in_state(GameState::Playing)),
             ));
         // app.insert_resource(TrashSpawnTimer(Timer::from_seconds(2.0, TimerMode::Repeating)))
         // app.add_systems(OnEnter(GameState::Playing), spawn_player)
@@ -20,109 +61,157 @@ impl Plugin for TypingPlugin {
 }
 
 
-pub fn typing(
-    // mut commands: Commands,
+impl Player {
+    pub const BOTH: [Player; 2] = [Player::One, Player::Two];
+
+    pub fn other(&self) -> Self {
+        match self {
+            Self::One => Self::Two,
+            Self::Two => Self::One,
+        }
+    }
+
+    /// The players of a run of this mode
+    pub fn playing(game_mode: &GameMode) -> &'static [Player] {
+        if *game_mode == GameMode::Versus { &Self::BOTH } else { &[Self::One] }
+    }
+}
+
+/// The player a trash belongs to
+pub fn owner(player_query: &Query<&Player>, trash: Entity) -> Player {
+    player_query.get(trash).copied().unwrap_or_default()
+}
+
+impl<T: Clone> PerPlayer<T> {
+    pub fn new(value: T) -> Self {
+        Self([value.clone(), value])
+    }
+}
+
+impl<T> Index<Player> for PerPlayer<T> {
+    type Output = T;
+
+    fn index(&self, player: Player) -> &T {
+        match player {
+            Player::One => &self.0[0],
+            Player::Two => &self.0[1],
+        }
+    }
+}
+
+impl<T> IndexMut<Player> for PerPlayer<T> {
+    fn index_mut(&mut self, player: Player) -> &mut T {
+        match player {
+            Player::One => &mut self.0[0],
+            Player::Two => &mut self.0[1],
+        }
+    }
+}
+
+/// The keys of a solo run all belong to `Player::One`
+impl From<KeyEdit> for TypingKey {
+    fn from(edit: KeyEdit) -> Self {
+        Self {
+            player: Player::One,
+            edit,
+        }
+    }
+}
+
+
+fn reset_typing_buffer(mut typing_buffer: ResMut<TypingBuffer>) {
+    *typing_buffer = TypingBuffer::default();
+}
+
+/// A buffer starts over once its word is completed or the trash it was typing is lost.
+/// Explosions only go off in solo runs.
+fn clear_typing_buffer(
     mut typing_buffer: ResMut<TypingBuffer>,
-    // mut combo_meter_query: Query<&mut ProgressBar, With<ComboMeter>>,
-    // mut combo_modifier: ResMut<ComboModifier>,
-    keyboard_input: Res<Input<KeyCode>>,
-    trash_query: Query<(&Parent, &TrashText)>,
-    // marked_trash_query: Query<Entity, With<TrashMarked>>,
+    mut word_completed_events: EventReader<WordCompleted>,
+    mut trash_landed_events: EventReader<TrashLanded>,
+    mut trash_destroyed_events: EventReader<TrashDestroyed>,
 ) {
+    let word_completed = word_completed_events.read().map(|event| event.player);
+    let marked_trash_landed = trash_landed_events.read().filter(|event| event.marked).map(|event| event.player);
+    let marked_trash_destroyed = trash_destroyed_events.read().filter(|event| event.marked).map(|_| Player::One);
 
-    if keyboard_input.pressed(KeyCode::ControlLeft) {
-        if keyboard_input.just_pressed(KeyCode::Back) {
-            typing_buffer.0 = "".to_string();
-            // remove_all_marked_trash(commands, marked_trash_query);
-            return
+    for player in word_completed.chain(marked_trash_landed).chain(marked_trash_destroyed) {
+        typing_buffer.0[player] = "".to_string();
+    }
+}
+
+
+/// Reads the keys that edit the typing buffer, replays feed the same keys from a file instead.
+/// The letters come from the text input of the OS so they follow the keyboard layout and the IME.
+pub fn read_typing_keys(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut received_characters: EventReader<ReceivedCharacter>,
+    target_lock: Res<TargetLock>,
+    typing_buffer: Res<TypingBuffer>,
+    game_mode: Res<GameMode>,
+    mut typing_keys: EventWriter<TypingKey>,
+) {
+    // Backspace and the ctrl shortcuts also come through as control characters,
+    // no word has a space so a space typed between two words isn't a miss.
+    // Phrases do have spaces, only the ones typed before the first letter of a phrase are dropped.
+    let spaces_typed = *game_mode == GameMode::Phrases;
+    let mut nothing_typed = typing_buffer.0[Player::One].is_empty();
+    let mut letters: Vec<char> = Vec::new();
+    for character in received_characters.read().map(|received_character| received_character.char) {
+        if character.is_control() || (character == ' ' && (!spaces_typed || nothing_typed)) {
+            continue;
         }
+
+        nothing_typed = false;
+        letters.push(character);
+    }
+
+    if keyboard_input.pressed(KeyCode::ControlLeft) && keyboard_input.just_pressed(KeyCode::Back) {
+        typing_keys.send(KeyEdit::Clear.into());
+        return;
     }
 
-    let mut buffer_word = typing_buffer.0.clone();
-    // let mut did_delete_letter = false;
-
-    for key in keyboard_input.get_just_pressed() {
-        match key {
-            KeyCode::A => buffer_word.push('a'),
-            KeyCode::B => buffer_word.push('b'),
-            KeyCode::C => buffer_word.push('c'),
-            KeyCode::D => buffer_word.push('d'),
-            KeyCode::E => buffer_word.push('e'),
-            KeyCode::F => buffer_word.push('f'),
-            KeyCode::G => buffer_word.push('g'),
-            KeyCode::H => buffer_word.push('h'),
-            KeyCode::I => buffer_word.push('i'),
-            KeyCode::J => buffer_word.push('j'),
-            KeyCode::K => buffer_word.push('k'),
-            KeyCode::L => buffer_word.push('l'),
-            KeyCode::M => buffer_word.push('m'),
-            KeyCode::N => buffer_word.push('n'),
-            KeyCode::O => buffer_word.push('o'),
-            KeyCode::P => buffer_word.push('p'),
-            KeyCode::Q => buffer_word.push('q'),
-            KeyCode::R => buffer_word.push('r'),
-            KeyCode::S => buffer_word.push('s'),
-            KeyCode::T => buffer_word.push('t'),
-            KeyCode::U => buffer_word.push('u'),
-            KeyCode::V => buffer_word.push('v'),
-            KeyCode::W => buffer_word.push('w'),
-            KeyCode::X => buffer_word.push('x'),
-            KeyCode::Y => buffer_word.push('y'),
-            KeyCode::Z => buffer_word.push('z'),
-            KeyCode::Back => { 
-                let _ = buffer_word.pop(); 
-                // did_delete_letter = true;
+    // With a locked target Esc clears the buffer (which releases the lock) instead of pausing
+    if keyboard_input.just_pressed(KeyCode::Escape) && target_lock.target.is_some() {
+        typing_keys.send(KeyEdit::Clear.into());
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        typing_keys.send(KeyEdit::Backspace.into());
+    }
+
+    for letter in letters {
+        typing_keys.send(KeyEdit::Letter(letter).into());
+    }
+}
+
+/// The capital of the character when `shift` is held, unless it has none or it's more than one character (ß)
+pub fn shifted(character: char, shift: bool) -> char {
+    let mut upper = character.to_uppercase();
+    match (shift, upper.next(), upper.next()) {
+        (true, Some(capital), None) => capital,
+        _ => character,
+    }
+}
+
+pub fn typing(
+    mut typing_buffer: ResMut<TypingBuffer>,
+    mut typing_keys: EventReader<TypingKey>,
+) {
+    let mut buffer_words = typing_buffer.0.clone();
+
+    for typing_key in typing_keys.read() {
+        let buffer_word = &mut buffer_words[typing_key.player];
+        match typing_key.edit {
+            KeyEdit::Letter(letter) => buffer_word.push(letter),
+            KeyEdit::Backspace => {
+                let _ = buffer_word.pop();
             },
-            _ => {}
+            KeyEdit::Clear | KeyEdit::Target { .. } => buffer_word.clear(),
         }
     }
 
-    if buffer_word != typing_buffer.0 {
-        typing_buffer.0 = buffer_word;
-        // return;
-    }
-
-
-    // let mut to_be_removed = vec![];
-    // let mut is_existing_matching_word = false;
-
-    // for (_, trash_text) in trash_query.iter() {
-    //     if trash_text.word.starts_with(&buffer_word) {
-    //         // if marked_trash_query.get(parent.get()).is_err() {
-    //             // commands.entity(parent.get()).insert(TrashMarked);
-    //         // }
-    //         typing_buffer.0 = buffer_word.clone();
-    //         return;
-    //     }
-
-        // } else {
-        //     if marked_trash_query.get(parent.get()).is_ok() {
-        //         to_be_removed.push(parent.get());
-        //     }
-        // }
-    // }
-
-    // TODO: Make the combo meter reset only for words that the player tried to type and then
-    // failed, not for every trash that lands. It's too hard
-
-    // if is_existing_matching_word {
-    //     typing_buffer.0 = buffer_word.clone();
-    //     if !did_delete_letter {
-    //         for mut progress_bar in &mut combo_meter_query.iter_mut() {
-    //             progress_bar.increase_progress(0.1);
-    //         }
-    //     }
-    //
-    //     for entity in to_be_removed {
-    //         commands.entity(entity).remove::<TrashMarked>();
-    //     }
-    //
-    // } else {
-    //     for mut progress_bar in &mut combo_meter_query.iter_mut() {
-    //         progress_bar.reset();
-    //         combo_modifier.0 = 1;
-    //    }
-    //
-    // }
-
+    if buffer_words != typing_buffer.0 {
+        typing_buffer.0 = buffer_words;
+    }
 }
@@ -0,0 +1,237 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{GameState, PauseState};
+use crate::events::{CorrectKey, WrongKey};
+use crate::game_mode::is_solo;
+use crate::game_over::GameOver;
+use crate::high_score::RunStats;
+use crate::settings::{KeyboardLayout, Settings};
+use crate::typing::shifted;
+
+pub struct TypingStatsPlugin;
+
+// The usual definition of a word when measuring typing speed
+const CHARACTERS_PER_WORD: f32 = 5.0;
+const HEATMAP_KEY_SIZE: f32 = 24.0;
+const UNMISSED_KEY_COLOR: Color = Color::rgb(0.25, 0.25, 0.25);
+const MOST_MISSED_KEY_COLOR: Color = Color::rgb(0.9, 0.1, 0.1);
+
+
+/// The typing performance of the current run
+#[derive(Resource, Default)]
+pub struct TypingStats {
+    pub correct_keys: usize,
+    pub wrong_keys: usize,
+    pub streak: usize,
+    pub longest_streak: usize,
+    pub misses: BTreeMap<char, usize>,
+}
+
+/// What's left of `TypingStats` once the run is over, saved with the high score entry
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct TypingSummary {
+    pub chars_per_minute: f32,
+    pub words_per_minute: f32,
+    /// From 0 to 1
+    pub accuracy: f32,
+    pub longest_streak: usize,
+    pub misses: BTreeMap<char, usize>,
+}
+
+
+impl Plugin for TypingStatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TypingStats>()
+        .add_systems(OnEnter(GameState::Playing), reset_typing_stats)
+        // The keys of both versus players are counted together, there's nothing to show for either one
+        .add_systems(OnEnter(GameState::GameOver), spawn_typing_summary.run_if(is_solo))
+        .add_systems(Update, (
+                update_typing_stats.run_if(in_state(GameState::Playing)).run_if(in_state(PauseState::Running)),
+            ));
+    }
+}
+
+
+impl TypingStats {
+    pub fn summary(&self, duration_seconds: f32) -> TypingSummary {
+        let minutes = duration_seconds / 60.0;
+        let chars_per_minute = if minutes > 0.0 { self.correct_keys as f32 / minutes } else { 0.0 };
+        let typed_keys = self.correct_keys + self.wrong_keys;
+
+        TypingSummary {
+            chars_per_minute,
+            words_per_minute: chars_per_minute / CHARACTERS_PER_WORD,
+            accuracy: if typed_keys > 0 { self.correct_keys as f32 / typed_keys as f32 } else { 1.0 },
+            longest_streak: self.longest_streak,
+            misses: self.misses.clone(),
+        }
+    }
+}
+
+
+fn reset_typing_stats(mut typing_stats: ResMut<TypingStats>) {
+    *typing_stats = TypingStats::default();
+}
+
+fn update_typing_stats(
+    mut typing_stats: ResMut<TypingStats>,
+    mut correct_key_events: EventReader<CorrectKey>,
+    mut wrong_key_events: EventReader<WrongKey>,
+) {
+    for _ in correct_key_events.read() {
+        typing_stats.correct_keys += 1;
+        typing_stats.streak += 1;
+        typing_stats.longest_streak = typing_stats.longest_streak.max(typing_stats.streak);
+    }
+
+    for wrong_key in wrong_key_events.read() {
+        // An emptied buffer isn't a key the player missed
+        let Some(letter) = wrong_key.letter else {
+            continue;
+        };
+
+        typing_stats.wrong_keys += 1;
+        typing_stats.streak = 0;
+        *typing_stats.misses.entry(letter).or_default() += 1;
+    }
+}
+
+/// The keys of the heatmap: the rows of the layout, then the missed characters that aren't on it
+/// (digits, accented letters) in rows of the same length
+pub fn heatmap_rows(layout: KeyboardLayout, misses: &BTreeMap<char, usize>) -> Vec<Vec<char>> {
+    let mut rows: Vec<Vec<char>> = layout.rows().iter().map(|row| row.chars().collect()).collect();
+    let row_length = rows[0].len();
+
+    let mut off_layout: Vec<char> = misses.keys()
+        .flat_map(|letter| letter.to_lowercase())
+        .filter(|key| rows.iter().all(|row| !row.contains(key)))
+        .collect();
+    off_layout.sort();
+    off_layout.dedup();
+    rows.extend(off_layout.chunks(row_length).map(|chunk| chunk.to_vec()));
+
+    rows
+}
+
+fn spawn_typing_summary(
+    mut commands: Commands,
+    typing_stats: Res<TypingStats>,
+    run_stats: Res<RunStats>,
+    settings: Res<Settings>,
+) {
+    let summary = typing_stats.summary(run_stats.duration_seconds);
+
+    commands.spawn(
+        TextBundle {
+            text: Text::from_section(
+                format!(
+                    "WPM: {:.0}  CPM: {:.0}  Accuracy: {:.0}%  Streak: {}",
+                    summary.words_per_minute,
+                    summary.chars_per_minute,
+                    summary.accuracy * 100.0,
+                    summary.longest_streak,
+                ),
+                TextStyle {
+                    font_size: 25.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                    ..default()
+                }
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(195.0),
+                left: Val::Px(120.0),
+                ..default()
+            },
+            ..default()
+        },
+    )
+    .insert(GameOver);
+
+    spawn_miss_heatmap(&mut commands, &summary.misses, settings.keyboard_layout);
+}
+
+/// A keyboard where the keys get redder the more they were missed
+fn spawn_miss_heatmap(commands: &mut Commands, misses: &BTreeMap<char, usize>, layout: KeyboardLayout) {
+    // Capitals are missed on the same key as their lowercase letter
+    let key_misses = |key: char| -> usize {
+        misses.iter()
+            .filter(|(letter, _)| letter.to_lowercase().eq(key.to_lowercase()))
+            .map(|(_, count)| count)
+            .sum()
+    };
+    let rows = heatmap_rows(layout, misses);
+    let most_misses = rows.iter()
+        .flatten()
+        .map(|key| key_misses(*key))
+        .max()
+        .unwrap_or(0)
+        .max(1) as f32;
+
+    commands.spawn(
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                top: Val::Px(10.0),
+                left: Val::Px(420.0),
+                ..default()
+            },
+            ..default()
+        }
+    )
+    .insert(GameOver)
+    .with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            "Missed keys",
+            TextStyle {
+                font_size: 18.0,
+                color: Color::GRAY,
+                ..default()
+            },
+        ));
+
+        for row in rows {
+            parent.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|row_parent| {
+                for key in row {
+                    let heat = key_misses(key) as f32 / most_misses;
+                    let color = Vec4::from(UNMISSED_KEY_COLOR).lerp(Vec4::from(MOST_MISSED_KEY_COLOR), heat);
+
+                    row_parent.spawn(NodeBundle {
+                        style: Style {
+                            width: Val::Px(HEATMAP_KEY_SIZE),
+                            height: Val::Px(HEATMAP_KEY_SIZE),
+                            margin: UiRect::all(Val::Px(1.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: Color::from(color).into(),
+                        ..default()
+                    })
+                    .with_children(|key_parent| {
+                        key_parent.spawn(TextBundle::from_section(
+                            shifted(key, true).to_string(),
+                            TextStyle {
+                                font_size: 16.0,
+                                color: Color::WHITE,
+                                ..default()
+                            },
+                        ));
+                    });
+                }
+            });
+        }
+    });
+}
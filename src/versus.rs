@@ -0,0 +1,354 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{GameState, PauseState};
+use crate::catalogue::{TrashCatalogue, TrashKind};
+use crate::difficulty::RunDifficulty;
+use crate::events::WordCompleted;
+use crate::game::{DifficultyLevel, GAMEPAD_TRASH_SPAWN_SPEED, TrashSpawnTimer, WINDOW_HEIGHT, WINDOW_WIDTH, choose_word, get_random_coordinate, slow_spawns, spawn_single_trash};
+use crate::game_mode::GameMode;
+use crate::game_over::{GameOver, GameOverLine};
+use crate::loading::DataAssets;
+use crate::power_ups::PowerUp;
+use crate::replay::is_live;
+use crate::score::{ComboModifier, Score};
+use crate::seed::{RunRng, RunSeed};
+use crate::settings::Settings;
+use crate::trash::{Trash, TrashActionActive, TrashBundle, handle_trash_collision};
+use crate::trash_text::TrashText;
+use crate::typing::{KeyEdit, PerPlayer, Player, TypingKey, owner, typing};
+use crate::words::{AvailableWords, build_available_words};
+
+pub struct VersusPlugin;
+
+/// Each side gets a quarter of the window on both sides of its center
+pub const VERSUS_FIELD_HALF_WIDTH: f32 = WINDOW_WIDTH / 4.0;
+
+// Clearing a word at this combo sends a garbage trash to the other side, and one more per level above it
+const GARBAGE_COMBO: usize = 3;
+const MAX_GARBAGE: usize = 4;
+
+const LEFT_COLOR: Color = Color::rgb(1.0, 0.6, 0.3);
+const RIGHT_COLOR: Color = Color::rgb(0.4, 0.7, 1.0);
+
+
+/// The half of the keyboard of a versus player and the words it can type,
+/// the typing buffer, score and combo are the same as a single player's (see `Player`)
+#[derive(Default)]
+pub struct VersusPlayer {
+    /// The keys of this player's half of the keyboard, from the layout in the settings
+    keys: String,
+    /// The words the keys of this player can type
+    words: AvailableWords,
+    previous_spawn_position: f32,
+}
+
+#[derive(Resource, Default)]
+pub struct VersusPlayers(PerPlayer<VersusPlayer>);
+
+/// The player whose pile reached their line first
+#[derive(Resource, Default)]
+pub struct VersusLoser(pub Option<Player>);
+
+#[derive(Component)]
+struct VersusHud(Player);
+
+
+/// Two playfields side by side, player one types with the left half of the keyboard and player two with the right half.
+/// Clearing words with a high combo drops garbage trash on the other side.
+impl Plugin for VersusPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<VersusPlayers>()
+        .init_resource::<VersusLoser>()
+        .add_systems(OnEnter(GameState::Playing), setup_versus
+            .after(build_available_words)
+            .run_if(resource_equals(GameMode::Versus)))
+        .add_systems(OnEnter(GameState::GameOver), spawn_versus_result.run_if(resource_equals(GameMode::Versus)))
+        .add_systems(Update, (
+                spawn_versus_trash,
+                read_versus_keys.before(typing).run_if(is_live),
+                drop_garbage.after(spawn_versus_trash).after(handle_trash_collision),
+                find_versus_loser,
+                update_versus_hud,
+            )
+            .run_if(in_state(GameState::Playing)).run_if(in_state(PauseState::Running))
+            .run_if(resource_equals(GameMode::Versus)));
+    }
+}
+
+
+/// The x of the middle of the playfield of a player
+pub fn field_center_x(player: Player) -> f32 {
+    match player {
+        Player::One => -VERSUS_FIELD_HALF_WIDTH,
+        Player::Two => VERSUS_FIELD_HALF_WIDTH,
+    }
+}
+
+fn player_label(player: Player) -> &'static str {
+    match player {
+        Player::One => "Player 1",
+        Player::Two => "Player 2",
+    }
+}
+
+fn player_color(player: Player) -> Color {
+    match player {
+        Player::One => LEFT_COLOR,
+        Player::Two => RIGHT_COLOR,
+    }
+}
+
+impl VersusPlayers {
+    /// The player whose half of the keyboard has this character (or its lowercase)
+    pub fn typing(&self, character: char) -> Option<Player> {
+        let lowercase: String = character.to_lowercase().collect();
+        Player::BOTH.into_iter().find(|player| self.0[*player].keys.contains(&lowercase))
+    }
+}
+
+
+fn setup_versus(
+    mut commands: Commands,
+    mut players: ResMut<VersusPlayers>,
+    mut loser: ResMut<VersusLoser>,
+    available_words: Res<AvailableWords>,
+    settings: Res<Settings>,
+) {
+    loser.0 = None;
+    for player in Player::BOTH {
+        let keys = match player {
+            Player::One => settings.keyboard_layout.left_half(),
+            Player::Two => settings.keyboard_layout.right_half(),
+        };
+        let words = available_words.typed_with(&keys);
+        if words.0.is_empty() {
+            warn!("None of the words of the run can be typed with {keys:?}, {} won't get any trash", player_label(player));
+        }
+
+        players.0[player] = VersusPlayer {
+            keys,
+            words,
+            ..default()
+        };
+
+        let (left, right) = match player {
+            Player::One => (Val::Px(10.0), Val::Auto),
+            Player::Two => (Val::Auto, Val::Px(10.0)),
+        };
+        commands.spawn((
+            TextBundle {
+                text: Text::from_section(
+                    hud_text(player, 0, 1),
+                    TextStyle {
+                        font_size: 30.0,
+                        color: player_color(player),
+                        ..default()
+                    },
+                ),
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(0.0),
+                    left,
+                    right,
+                    ..default()
+                },
+                ..default()
+            },
+            VersusHud(player),
+        ));
+    }
+}
+
+fn hud_text(player: Player, score: usize, combo: usize) -> String {
+    format!("{}: {score} x{combo}", player_label(player))
+}
+
+/// Both sides get a trash at the same time so neither has more to type
+#[allow(clippy::too_many_arguments)]
+fn spawn_versus_trash(
+    mut commands: Commands,
+    data_assets: Res<DataAssets>,
+    catalogues: Res<Assets<TrashCatalogue>>,
+    time: Res<Time>,
+    difficulty_level: Res<DifficultyLevel>,
+    run_difficulty: Res<RunDifficulty>,
+    settings: Res<Settings>,
+    run_seed: Res<RunSeed>,
+    game_mode: Res<GameMode>,
+    mut run_rng: ResMut<RunRng>,
+    mut players: ResMut<VersusPlayers>,
+    mut spawn_timer: ResMut<TrashSpawnTimer>,
+    words_on_screen: Query<(&Parent, &TrashText)>,
+    player_query: Query<&Player>,
+) {
+    let spawn_speed = if slow_spawns(&settings, &run_seed) { GAMEPAD_TRASH_SPAWN_SPEED } else { 1.0 };
+    if !spawn_timer.0.tick(time.delta().mul_f32(spawn_speed)).just_finished() {
+        return;
+    }
+
+    let catalogue = catalogues.get(&data_assets.trash_catalogue).expect("the catalogue is loaded before leaving GameState::Loading");
+    let difficulty = run_difficulty.profile.at(difficulty_level.0);
+    let RunRng { trash: random, words: words_random } = &mut *run_rng;
+
+    for player in Player::BOTH {
+        let mut taken_words: Vec<String> = words_on_screen.iter()
+            .filter(|(parent, _)| owner(&player_query, parent.get()) == player)
+            .map(|(_, trash_text)| trash_text.word.clone())
+            .collect();
+        if taken_words.len() >= difficulty.max_trash {
+            continue;
+        }
+
+        let versus_player = &mut players.0[player];
+        let Some(word) = choose_word(&versus_player.words, &settings, &run_seed, &game_mode, words_random, difficulty.word_difficulty, &mut taken_words, Some(versus_player.keys.as_str())) else {
+            continue;
+        };
+
+        let x = get_random_coordinate(random, VERSUS_FIELD_HALF_WIDTH, versus_player.previous_spawn_position);
+        versus_player.previous_spawn_position = x;
+        let kind = catalogue.choose(random).clone();
+        spawn_single_trash(&mut commands, &settings, word, kind, PowerUp::None, Vec3::new(field_center_x(player) + x, WINDOW_HEIGHT, 0.0))
+            .insert(Velocity::linear(Vec2::new(0.0, -difficulty.fall_speed)))
+            .insert(player);
+    }
+}
+
+/// Every typed character goes to the player whose half of the keyboard it's on,
+/// their keys then go through the same typing and scoring as a single player's.
+/// Tab and backspace delete the last letter of player one and player two.
+pub fn read_versus_keys(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut received_characters: EventReader<ReceivedCharacter>,
+    players: Res<VersusPlayers>,
+    mut typing_keys: EventWriter<TypingKey>,
+) {
+    for (key, player) in [(KeyCode::Tab, Player::One), (KeyCode::Back, Player::Two)] {
+        if keyboard_input.just_pressed(key) {
+            typing_keys.send(TypingKey { player, edit: KeyEdit::Backspace });
+        }
+    }
+
+    for character in received_characters.read().map(|received_character| received_character.char) {
+        if let Some(player) = players.typing(character) {
+            typing_keys.send(TypingKey { player, edit: KeyEdit::Letter(character) });
+        }
+    }
+}
+
+/// A word cleared with a high combo drops garbage on the other side
+#[allow(clippy::too_many_arguments)]
+fn drop_garbage(
+    mut commands: Commands,
+    mut word_completed_events: EventReader<WordCompleted>,
+    combo_modifier: Res<ComboModifier>,
+    mut run_rng: ResMut<RunRng>,
+    data_assets: Res<DataAssets>,
+    catalogues: Res<Assets<TrashCatalogue>>,
+    difficulty_level: Res<DifficultyLevel>,
+    run_difficulty: Res<RunDifficulty>,
+) {
+    let catalogue = catalogues.get(&data_assets.trash_catalogue).expect("the catalogue is loaded before leaving GameState::Loading");
+    let fall_speed = run_difficulty.profile.at(difficulty_level.0).fall_speed;
+
+    for word_completed in word_completed_events.read() {
+        let garbage = (combo_modifier.0[word_completed.player] + 1).saturating_sub(GARBAGE_COMBO).min(MAX_GARBAGE);
+        let target = word_completed.player.other();
+        let mut previous = 0.0;
+        for _ in 0..garbage {
+            let x = get_random_coordinate(&mut run_rng.trash, VERSUS_FIELD_HALF_WIDTH, previous);
+            previous = x;
+            let kind = catalogue.choose(&mut run_rng.trash).clone();
+            spawn_garbage(&mut commands, kind, Vec3::new(field_center_x(target) + x, WINDOW_HEIGHT, 0.0), fall_speed, target);
+        }
+    }
+}
+
+/// A trash without a word, it falls like the others and joins the pile when it lands
+fn spawn_garbage(commands: &mut Commands, kind: TrashKind, position: Vec3, fall_speed: f32, player: Player) {
+    commands.spawn(TrashBundle::new(Trash::new(kind, PowerUp::None)))
+        .insert(Transform::from_translation(position))
+        .insert(Velocity::linear(Vec2::new(0.0, -fall_speed)))
+        .insert(TrashActionActive)
+        .insert(player);
+}
+
+/// The pile touching a line ends the run (see `handle_trash_collision`), this keeps whose line it was
+fn find_versus_loser(
+    mut loser: ResMut<VersusLoser>,
+    mut collision_events: EventReader<CollisionEvent>,
+    line_query: Query<&Player, With<GameOverLine>>,
+    pile_query: Query<(), (With<Trash>, Without<TrashActionActive>)>,
+) {
+    for collision_event in collision_events.read() {
+        let CollisionEvent::Started(entity1, entity2, _) = collision_event else {
+            continue;
+        };
+
+        for (line, other) in [(entity1, entity2), (entity2, entity1)] {
+            if let (Ok(player), true) = (line_query.get(*line), pile_query.contains(*other)) {
+                loser.0.get_or_insert(*player);
+            }
+        }
+    }
+}
+
+fn update_versus_hud(
+    score: Res<Score>,
+    combo_modifier: Res<ComboModifier>,
+    mut hud_query: Query<(&mut Text, &VersusHud)>,
+) {
+    if !score.is_changed() && !combo_modifier.is_changed() {
+        return;
+    }
+
+    for (mut text, hud) in hud_query.iter_mut() {
+        text.sections[0].value = hud_text(hud.0, score.0[hud.0], combo_modifier.0[hud.0]);
+    }
+}
+
+fn spawn_versus_result(
+    mut commands: Commands,
+    score: Res<Score>,
+    combo_modifier: Res<ComboModifier>,
+    loser: Res<VersusLoser>,
+) {
+    let (title, color) = match loser.0 {
+        Some(player) => (format!("{} wins", player_label(player.other())), player_color(player.other())),
+        None => ("Draw".to_string(), Color::WHITE),
+    };
+
+    commands.spawn(
+        TextBundle {
+            text: Text::from_sections([
+                TextSection {
+                    value: format!("{title}\n"),
+                    style: TextStyle {
+                        font_size: 50.0,
+                        color,
+                        ..default()
+                    },
+                },
+                TextSection {
+                    value: Player::BOTH.iter()
+                        .map(|player| hud_text(*player, score.0[*player], combo_modifier.0[*player]))
+                        .collect::<Vec<String>>()
+                        .join("   "),
+                    style: TextStyle {
+                        font_size: 25.0,
+                        color: Color::rgb(0.9, 0.9, 0.9),
+                        ..default()
+                    },
+                },
+            ]),
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(100.0),
+                left: Val::Px(120.0),
+                ..default()
+            },
+            ..default()
+        },
+    )
+    .insert(GameOver);
+}
@@ -0,0 +1,628 @@
+use std::collections::BTreeSet;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext, io::Reader};
+use bevy::prelude::*;
+use bevy::reflect::TypePath;
+use bevy::utils::{BoxedFuture, HashSet};
+use rand::Rng;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::GameState;
+use crate::game_mode::GameMode;
+use crate::loading::DataAssets;
+use crate::menu::{ButtonColors, TextInput, spawn_text_input};
+use crate::phrases::{PhraseList, PhraseListLoader, build_available_phrases, phrase_files_from_arguments, read_phrase_file};
+use crate::seed::{RunSeed, seed_run};
+
+pub struct WordsPlugin;
+
+const DEFAULT_WORD_PACK: &str = "everyday";
+const MAX_CUSTOM_WORDS_LENGTH: usize = 200;
+const ENABLED_PACK_COLOR: Color = Color::rgb(0.15, 0.45, 0.15);
+// How often each enabled `WordStyle` twist is applied to a word
+const WORD_STYLE_CHANCE: f64 = 0.25;
+const PUNCTUATION: [char; 6] = ['.', ',', '!', '?', ';', ':'];
+const MAX_NUMBER: u32 = 10_000;
+
+
+/// A list of words that can appear on trash, loaded from a `.words` file with one word per line
+#[derive(Asset, TypePath, Debug)]
+pub struct WordList {
+    pub name: String,
+    pub words: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct WordListLoader;
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum WordListLoaderError {
+    #[error("Could not read the word list: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("The word list doesn't contain any valid word")]
+    Empty,
+}
+
+#[derive(Debug, Clone)]
+pub struct AvailableWord {
+    pub word: String,
+    /// Percentile of how rare the letters of the word are compared to the rest of the words, 0 is the most common
+    pub rarity: f32,
+}
+
+/// The words of the current run
+#[derive(Resource, Default)]
+pub struct AvailableWords(pub Vec<AvailableWord>);
+
+/// Where the words of the next run come from, every enabled source is merged together
+#[derive(Resource, Clone)]
+pub struct WordSources {
+    pub enabled_packs: HashSet<String>,
+    pub files: Vec<PathBuf>,
+    pub custom_words: String,
+    /// The `--phrases` files, only used by `GameMode::Phrases`
+    pub phrase_files: Vec<PathBuf>,
+    /// Phrases that don't come from a file, replays of phrase runs bring theirs here
+    pub custom_phrases: Vec<String>,
+}
+
+/// The sources the words of the current run actually came from, phrase runs keep their phrase lists here
+#[derive(Resource, Default)]
+pub struct RunWordSources {
+    pub packs: Vec<String>,
+    /// Words from the `--words` files and the custom words, or phrases from the `--phrases` files
+    pub extra_words: Vec<String>,
+}
+
+/// Twists on the words of the lists for practicing more than lowercase letters,
+/// the non-English alphabets come from the word lists themselves
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(default)]
+pub struct WordStyle {
+    /// Capitalizes the first letter of some words
+    pub capitals: bool,
+    /// Ends some words with a punctuation mark
+    pub punctuation: bool,
+    /// Replaces some words with numbers
+    pub numbers: bool,
+}
+
+#[derive(Component)]
+struct WordsMenu;
+
+#[derive(Component)]
+struct WordPackToggle(String);
+
+#[derive(Component)]
+struct CustomWordsInput;
+
+
+impl Plugin for WordsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<WordList>()
+        .init_asset_loader::<WordListLoader>()
+        .init_asset::<PhraseList>()
+        .init_asset_loader::<PhraseListLoader>()
+        .init_resource::<AvailableWords>()
+        .init_resource::<RunWordSources>()
+        .insert_resource(WordSources {
+            enabled_packs: HashSet::from([DEFAULT_WORD_PACK.to_string()]),
+            files: word_files_from_arguments(),
+            custom_words: "".to_string(),
+            phrase_files: phrase_files_from_arguments(),
+            custom_phrases: Vec::new(),
+        })
+        .add_systems(OnEnter(GameState::Menu), spawn_word_sources_menu)
+        .add_systems(OnEnter(GameState::Playing), build_available_words.after(seed_run))
+        .add_systems(Update, (
+                toggle_word_packs.run_if(in_state(GameState::Menu)),
+                update_custom_words.run_if(in_state(GameState::Menu)),
+            ))
+        .add_systems(OnExit(GameState::Menu), cleanup_word_sources_menu);
+    }
+}
+
+
+impl WordSources {
+    /// The words of the sources that aren't packs
+    pub fn extra_words(&self) -> Vec<String> {
+        let mut words: Vec<String> = Vec::new();
+        for path in self.files.iter() {
+            words.extend(read_word_file(path));
+        }
+
+        words.extend(
+            self.custom_words
+                .split_whitespace()
+                .filter(|word| is_valid_word(word))
+                .map(|word| word.to_string())
+        );
+
+        words
+    }
+
+    /// The phrases of the sources that aren't bundled lists
+    pub fn extra_phrases(&self) -> Vec<String> {
+        let mut phrases: Vec<String> = Vec::new();
+        for path in self.phrase_files.iter() {
+            phrases.extend(read_phrase_file(path));
+        }
+        phrases.extend(self.custom_phrases.iter().cloned());
+
+        phrases
+    }
+}
+
+impl AvailableWords {
+    pub fn from_words(words: impl IntoIterator<Item = String>) -> Self {
+        let mut unique_words: Vec<String> = words.into_iter().filter(|word| !word.is_empty()).collect();
+        unique_words.sort();
+        unique_words.dedup();
+
+        let mut scored_words: Vec<(String, f32)> = unique_words.into_iter()
+            .map(|word| {
+                let score = letter_rarity(&word);
+                (word, score)
+            })
+            .collect();
+        scored_words.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+        let last_index = scored_words.len().saturating_sub(1).max(1) as f32;
+        Self(
+            scored_words.into_iter()
+                .enumerate()
+                .map(|(index, (word, _))| AvailableWord {
+                    word,
+                    rarity: index as f32 / last_index,
+                })
+                .collect()
+        )
+    }
+
+    /// Picks a word for a new trash.
+    /// `difficulty` goes from 0 (short words with common letters) to 1 (long words with rare letters),
+    /// the length range it picks is kept within the player's `length_limits`,
+    /// words that clash with one of the `taken_words` are skipped (see `is_free`).
+    /// Returns `None` when every word clashes or is out of the `length_limits`.
+    pub fn choose(
+        &self,
+        difficulty: f32,
+        length_limits: RangeInclusive<usize>,
+        taken_words: &[String],
+        random: &mut impl Rng,
+    ) -> Option<String> {
+        let difficulty = difficulty.clamp(0.0, 1.0);
+        let (shortest, longest) = length_limits.into_inner();
+        let min_length = (2 + (3.0 * difficulty).round() as usize).clamp(shortest, longest);
+        let max_length = (5 + (9.0 * difficulty).round() as usize).clamp(min_length, longest);
+        let max_rarity = 0.5 + 0.5 * difficulty;
+
+        let is_free = |word: &AvailableWord| is_free(&word.word, taken_words);
+        let fits_length = |word: &AvailableWord| {
+            (min_length..=max_length).contains(&word.word.chars().count())
+        };
+        let fits_limits = |word: &AvailableWord| {
+            (shortest..=longest).contains(&word.word.chars().count())
+        };
+
+        // The rules coming from the difficulty are relaxed in turn when no word satisfies all of them,
+        // the clashes and the player's length limits are never relaxed,
+        // the clashes since they're what keeps every word typeable to its end
+        let passes: [&dyn Fn(&AvailableWord) -> bool; 3] = [
+            &|word| is_free(word) && fits_length(word) && word.rarity <= max_rarity,
+            &|word| is_free(word) && fits_length(word),
+            &|word| is_free(word) && fits_limits(word),
+        ];
+
+        for pass in passes {
+            let candidates: Vec<&AvailableWord> = self.0.iter().filter(|word| pass(word)).collect();
+            if let Some(word) = candidates.choose(random) {
+                return Some(word.word.clone());
+            }
+        }
+
+        None
+    }
+
+    /// Every lowercase character the run can ask for outside of a to z, sorted
+    pub fn extra_characters(&self, word_style: WordStyle) -> Vec<char> {
+        self.0.iter()
+            .flat_map(|available_word| available_word.word.chars())
+            .flat_map(|character| character.to_lowercase())
+            .chain(word_style.characters())
+            .filter(|character| !character.is_ascii_lowercase())
+            .collect::<BTreeSet<char>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// The words made only of the given lowercase characters (or their capitals), ranked again among themselves
+    pub fn typed_with(&self, characters: &str) -> Self {
+        Self::from_words(
+            self.0.iter()
+                .filter(|available_word| is_typed_with(&available_word.word, characters))
+                .map(|available_word| available_word.word.clone())
+        )
+    }
+}
+
+
+impl WordStyle {
+    /// Applies the enabled twists to a word picked from the lists,
+    /// a twist that would make the word clash with one of the `taken_words` is skipped
+    pub fn apply(&self, word: String, taken_words: &[String], random: &mut impl Rng) -> String {
+        let is_free = |word: &str| is_free(word, taken_words);
+
+        if self.numbers && random.gen_bool(WORD_STYLE_CHANCE) {
+            let number = random.gen_range(1..MAX_NUMBER).to_string();
+            if is_free(&number) {
+                return number;
+            }
+        }
+
+        let mut word = word;
+        if self.capitals && random.gen_bool(WORD_STYLE_CHANCE) {
+            let mut characters = word.chars();
+            let capitalized: String = characters.next().into_iter()
+                .flat_map(|letter| letter.to_uppercase())
+                .chain(characters)
+                .collect();
+            if is_free(&capitalized) {
+                word = capitalized;
+            }
+        }
+
+        if self.punctuation && random.gen_bool(WORD_STYLE_CHANCE) {
+            if let Some(mark) = PUNCTUATION.choose(random) {
+                let punctuated = format!("{word}{mark}");
+                if is_free(&punctuated) {
+                    word = punctuated;
+                }
+            }
+        }
+
+        word
+    }
+
+    /// The characters the twists add to the words (capitals aside)
+    pub fn characters(&self) -> Vec<char> {
+        let numbers = self.numbers.then_some('0'..='9').into_iter().flatten();
+        let punctuation = self.punctuation.then_some(PUNCTUATION).into_iter().flatten();
+
+        numbers.chain(punctuation).collect()
+    }
+}
+
+
+// Relative frequency (in percent) of the letters in english text
+const LETTER_FREQUENCIES: [(char, f32); 26] = [
+    ('e', 12.7), ('t', 9.1), ('a', 8.2), ('o', 7.5), ('i', 7.0), ('n', 6.7), ('s', 6.3),
+    ('h', 6.1), ('r', 6.0), ('d', 4.3), ('l', 4.0), ('c', 2.8), ('u', 2.8), ('m', 2.4),
+    ('w', 2.4), ('f', 2.2), ('g', 2.0), ('y', 2.0), ('p', 1.9), ('b', 1.5), ('v', 1.0),
+    ('k', 0.8), ('j', 0.15), ('x', 0.15), ('q', 0.1), ('z', 0.07),
+];
+
+/// The average rarity of the letters in the word, 0 for the most common letter and 1 for letters that aren't in the table
+fn letter_rarity(word: &str) -> f32 {
+    let max_frequency = LETTER_FREQUENCIES[0].1;
+    let total: f32 = word.chars()
+        .map(|character| {
+            let frequency = LETTER_FREQUENCIES.iter()
+                .find(|(letter, _)| *letter == character.to_ascii_lowercase())
+                .map_or(0.0, |(_, frequency)| *frequency);
+            1.0 - frequency / max_frequency
+        })
+        .sum();
+
+    total / word.chars().count().max(1) as f32
+}
+
+
+/// Words can use any alphabet, capitals, digits and punctuation, but no spaces or control characters
+pub fn is_valid_word(word: &str) -> bool {
+    !word.is_empty() && word.chars().all(|character| !character.is_whitespace() && !character.is_control())
+}
+
+/// Whether the word can fall while the `taken_words` are on screen: none of them may start with the other,
+/// or the shorter one would be completed while typing the longer one
+pub fn is_free(word: &str, taken_words: &[String]) -> bool {
+    taken_words.iter().all(|taken| !taken.starts_with(word) && !word.starts_with(taken.as_str()))
+}
+
+/// Whether every character of the word is one of the given lowercase characters (or their capital)
+pub fn is_typed_with(word: &str, characters: &str) -> bool {
+    word.chars().flat_map(char::to_lowercase).all(|character| characters.contains(character))
+}
+
+/// Splits the text into words, every line is a word.
+/// Returns the valid words and the amount of lines that were rejected (see `is_valid_word`).
+pub fn parse_words(text: &str) -> (Vec<String>, usize) {
+    let mut words = Vec::new();
+    let mut rejected = 0;
+
+    for line in text.split('\n') {
+        let word = line.trim_end_matches('\r').trim();
+        if word.is_empty() {
+            continue;
+        }
+
+        if is_valid_word(word) {
+            words.push(word.to_string());
+        } else {
+            rejected += 1;
+        }
+    }
+
+    (words, rejected)
+}
+
+
+impl AssetLoader for WordListLoader {
+    type Asset = WordList;
+    type Settings = ();
+    type Error = WordListLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut text = String::new();
+            reader.read_to_string(&mut text).await?;
+
+            let (words, rejected) = parse_words(&text);
+            if rejected > 0 {
+                warn!("Skipped {rejected} invalid words in {:?}", load_context.path());
+            }
+            if words.is_empty() {
+                return Err(WordListLoaderError::Empty);
+            }
+
+            let name = load_context
+                .path()
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+            Ok(WordList { name, words })
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["words"]
+    }
+}
+
+
+/// Word lists outside of the assets can be added with `--words <path>`
+fn word_files_from_arguments() -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut arguments = std::env::args().skip(1);
+
+    while let Some(argument) = arguments.next() {
+        if argument == "--words" {
+            if let Some(path) = arguments.next() {
+                files.push(PathBuf::from(path));
+            }
+        }
+    }
+
+    files
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_word_file(path: &PathBuf) -> Vec<String> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => {
+            let (words, rejected) = parse_words(&text);
+            if rejected > 0 {
+                warn!("Skipped {rejected} invalid words in {path:?}");
+            }
+            words
+        },
+        Err(error) => {
+            warn!("Failed to read word list {path:?}: {error}");
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_word_file(_path: &PathBuf) -> Vec<String> {
+    Vec::new()
+}
+
+
+#[allow(clippy::too_many_arguments)]
+pub fn build_available_words(
+    mut available_words: ResMut<AvailableWords>,
+    mut run_word_sources: ResMut<RunWordSources>,
+    word_sources: Res<WordSources>,
+    run_seed: Res<RunSeed>,
+    data_assets: Res<DataAssets>,
+    word_lists: Res<Assets<WordList>>,
+    phrase_lists: Res<Assets<PhraseList>>,
+    game_mode: Res<GameMode>,
+) {
+    if *game_mode == GameMode::Phrases {
+        (*run_word_sources, *available_words) = build_available_phrases(&word_sources, &run_seed, &data_assets, &phrase_lists);
+        return;
+    }
+
+    // Everyone playing the daily run gets the same words
+    *run_word_sources = if run_seed.is_daily() {
+        RunWordSources {
+            packs: vec![DEFAULT_WORD_PACK.to_string()],
+            extra_words: Vec::new(),
+        }
+    } else {
+        let mut packs: Vec<String> = word_sources.enabled_packs.iter().cloned().collect();
+        packs.sort();
+        RunWordSources {
+            packs,
+            extra_words: word_sources.extra_words(),
+        }
+    };
+
+    let packs: Vec<&WordList> = data_assets.word_lists.iter()
+        .filter_map(|handle| word_lists.get(handle))
+        .collect();
+
+    let mut words: Vec<String> = Vec::new();
+    for pack in packs.iter().filter(|pack| run_word_sources.packs.contains(&pack.name)) {
+        words.extend(pack.words.iter().cloned());
+    }
+    words.extend(run_word_sources.extra_words.iter().cloned());
+
+    if words.is_empty() {
+        warn!("No words in the selected word lists, falling back to the {DEFAULT_WORD_PACK} pack");
+        if let Some(pack) = packs.iter().find(|pack| pack.name == DEFAULT_WORD_PACK) {
+            words.extend(pack.words.iter().cloned());
+        }
+    }
+
+    *available_words = AvailableWords::from_words(words);
+}
+
+
+fn spawn_word_sources_menu(
+    mut commands: Commands,
+    word_sources: Res<WordSources>,
+    data_assets: Res<DataAssets>,
+    word_lists: Res<Assets<WordList>>,
+) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    top: Val::Percent(62.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            WordsMenu,
+        ))
+        .with_children(|children| {
+            children.spawn(TextBundle::from_section(
+                "Word lists",
+                TextStyle {
+                    font_size: 25.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                    ..default()
+                },
+            ));
+
+            children
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        margin: UiRect::top(Val::Px(5.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|row| {
+                    for handle in data_assets.word_lists.iter() {
+                        let Some(pack) = word_lists.get(handle) else {
+                            continue;
+                        };
+
+                        let button_colors = ButtonColors {
+                            normal: pack_color(word_sources.enabled_packs.contains(&pack.name)),
+                            ..default()
+                        };
+                        row
+                            .spawn((
+                                ButtonBundle {
+                                    style: Style {
+                                        height: Val::Px(36.0),
+                                        padding: UiRect::horizontal(Val::Px(10.0)),
+                                        margin: UiRect::horizontal(Val::Px(4.0)),
+                                        align_items: AlignItems::Center,
+                                        ..default()
+                                    },
+                                    background_color: button_colors.normal.into(),
+                                    ..default()
+                                },
+                                button_colors,
+                                WordPackToggle(pack.name.clone()),
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    pack.name.clone(),
+                                    TextStyle {
+                                        font_size: 20.0,
+                                        color: Color::rgb(0.9, 0.9, 0.9),
+                                        ..default()
+                                    },
+                                ));
+                            });
+                    }
+                });
+
+            spawn_text_input(children, Val::Px(420.0), TextInput {
+                value: word_sources.custom_words.clone(),
+                placeholder: "Your own words, separated by spaces",
+                max_length: MAX_CUSTOM_WORDS_LENGTH,
+                accepts: |character| !character.is_control(),
+                focused: false,
+            })
+            .insert(CustomWordsInput);
+        });
+}
+
+fn pack_color(enabled: bool) -> Color {
+    if enabled {
+        ENABLED_PACK_COLOR
+    } else {
+        ButtonColors::default().normal
+    }
+}
+
+fn toggle_word_packs(
+    mut word_sources: ResMut<WordSources>,
+    mut interaction_query: Query<
+        (&Interaction, &mut BackgroundColor, &mut ButtonColors, &WordPackToggle),
+        Changed<Interaction>,
+    >,
+) {
+    for (interaction, mut color, mut button_colors, toggle) in &mut interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        let enabled = !word_sources.enabled_packs.remove(&toggle.0);
+        if enabled {
+            word_sources.enabled_packs.insert(toggle.0.clone());
+        }
+
+        button_colors.normal = pack_color(enabled);
+        *color = button_colors.normal.into();
+    }
+}
+
+fn update_custom_words(
+    mut word_sources: ResMut<WordSources>,
+    custom_words_query: Query<&TextInput, (With<CustomWordsInput>, Changed<TextInput>)>,
+) {
+    for text_input in custom_words_query.iter() {
+        word_sources.custom_words = text_input.value.clone();
+    }
+}
+
+fn cleanup_word_sources_menu(mut commands: Commands, menu: Query<Entity, With<WordsMenu>>) {
+    for entity in menu.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}